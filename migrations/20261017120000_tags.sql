@@ -0,0 +1,5 @@
+ALTER TABLE bookmarks ADD COLUMN IF NOT EXISTS tags text[] NOT NULL DEFAULT '{}';
+ALTER TABLE apps ADD COLUMN IF NOT EXISTS tags text[] NOT NULL DEFAULT '{}';
+
+CREATE INDEX IF NOT EXISTS bookmarks_tags_idx ON bookmarks USING GIN (tags);
+CREATE INDEX IF NOT EXISTS apps_tags_idx ON apps USING GIN (tags);
@@ -0,0 +1,8 @@
+ALTER TABLE apps ADD COLUMN IF NOT EXISTS created_at timestamp with time zone;
+
+-- apps added before this column existed get the date the table was created,
+-- so that they don't all show up as new in the feed
+UPDATE apps SET created_at = '2023-08-21 17:15:30+00' WHERE created_at IS NULL;
+
+ALTER TABLE apps ALTER COLUMN created_at SET DEFAULT now();
+ALTER TABLE apps ALTER COLUMN created_at SET NOT NULL;
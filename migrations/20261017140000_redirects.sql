@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS redirects (
+  from_path text PRIMARY KEY,
+  to_path text NOT NULL,
+  created_at timestamp with time zone NOT NULL DEFAULT now()
+);
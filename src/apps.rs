@@ -1,8 +1,7 @@
 use futures::future::join_all;
-use rscx::html;
 use sqlx::{FromRow, PgPool};
 
-use crate::{images::ImageSrc, markdown::Markdown};
+use crate::{images::ImageSrc, markdown::render as render_markdown, mdx::ComponentRegistry};
 
 #[derive(Debug, FromRow)]
 pub struct AppRow {
@@ -11,6 +10,8 @@ pub struct AppRow {
     pub description: String,
     pub url: String,
     pub images: Vec<String>,
+    pub tags: Vec<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
 }
 
 #[derive(Debug)]
@@ -20,18 +21,20 @@ pub struct App {
     pub description: String,
     pub url: String,
     pub images: Vec<ImageSrc>,
+    pub tags: Vec<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
 }
 
 impl App {
-    async fn from_row(row: AppRow) -> Self {
+    async fn from_row(row: AppRow, components: &ComponentRegistry) -> Self {
         Self {
             slug: row.slug,
             name: row.name,
-            description: html! {
-                <Markdown source=row.description />
-            },
+            description: render_markdown(row.description, components.clone()).await,
             url: row.url,
             images: row.images.into_iter().map(From::from).collect(),
+            tags: row.tags,
+            created_at: row.created_at,
         }
     }
 }
@@ -39,11 +42,13 @@ impl App {
 #[derive(Clone)]
 pub struct AppsRepo {
     pool: PgPool,
+    components: ComponentRegistry,
 }
 
 impl AppsRepo {
-    pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+    #[must_use]
+    pub fn new(pool: PgPool, components: ComponentRegistry) -> Self {
+        Self { pool, components }
     }
 
     pub async fn list(&self) -> Vec<App> {
@@ -60,23 +65,66 @@ impl AppsRepo {
 
         join_all(
             res.into_iter()
-                .map(|row| async { App::from_row(row).await }),
+                .map(|row| async { App::from_row(row, &self.components).await }),
         )
         .await
     }
 
-    pub async fn get_by_slug(&self, slug: &str) -> Option<App> {
+    /// Lists the apps tagged with `tag`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the database can't be queried.
+    pub async fn list_by_tag(&self, tag: &str) -> Vec<App> {
         let mut conn = self.pool.acquire().await.unwrap();
         let res = sqlx::query_as::<_, AppRow>(
-            r#"
+            r"
+            select * from apps
+            where $1 = any(tags)
+            order by name asc
+        ",
+        )
+        .bind(tag)
+        .fetch_all(&mut conn)
+        .await
+        .unwrap();
+
+        join_all(
+            res.into_iter()
+                .map(|row| async { App::from_row(row, &self.components).await }),
+        )
+        .await
+    }
+
+    /// Returns the app with `slug`, if any.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the database can't be queried.
+    pub async fn get_by_slug(&self, slug: &str) -> Option<App> {
+        self.try_get_by_slug(slug).await.unwrap()
+    }
+
+    /// Like [`AppsRepo::get_by_slug`], but returns database errors instead
+    /// of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database can't be queried.
+    pub async fn try_get_by_slug(&self, slug: &str) -> Result<Option<App>, sqlx::Error> {
+        let mut conn = self.pool.acquire().await?;
+        let res = sqlx::query_as::<_, AppRow>(
+            r"
             select * from apps
             where slug = $1
-        "#,
+        ",
         )
         .bind(slug)
         .fetch_optional(&mut conn)
-        .await
-        .unwrap();
-        App::from_row(res?).await.into()
+        .await?;
+        Ok(match res {
+            Some(row) => Some(App::from_row(row, &self.components).await),
+            None => None,
+        })
     }
 }
@@ -1,54 +1,636 @@
-use chrono::{DateTime, FixedOffset};
-use rscx::html;
+use std::{
+    collections::{HashMap, HashSet},
+    future::Future,
+    path::Path,
+    sync::{Arc, Mutex, RwLock},
+    time::Duration,
+};
 
-use crate::markdown::{load_dir, Markdown};
+use chrono::{DateTime, FixedOffset, Utc};
+use notify_debouncer_mini::{new_debouncer, notify::RecursiveMode, DebounceEventResult};
+use scraper::Html;
+
+use crate::{
+    markdown::{
+        load_dir, render as render_markdown, slugify, table_of_contents, LoadError, MarkdownFile,
+        TocEntry,
+    },
+    mdx::ComponentRegistry,
+    redirects::normalize_path,
+    search::{html_to_text, tokenize},
+    tags::normalize_tag,
+    validate::{check_file, check_links},
+    wiki_links,
+};
+
+pub const ARTICLES_DIR: &str = "./articles";
+
+const WORDS_PER_MINUTE: usize = 200;
+
+/// Articles at least this long get their table of contents shown.
+const LONG_ARTICLE_WORDS: usize = 1000;
+
+/// Number of related articles shown below each article.
+const RELATED_ARTICLES: usize = 3;
 
 #[derive(Clone, Debug)]
 pub struct Article {
     pub title: String,
     pub datetime: DateTime<FixedOffset>,
+    /// When the article becomes visible, defaults to `datetime`.
+    pub publish_at: DateTime<FixedOffset>,
     pub slug: String,
+    /// The markdown file the article was loaded from, frontmatter included.
+    pub source: String,
     pub content: String,
     pub unlisted: bool,
+    pub tags: Vec<String>,
+    pub series: Option<Series>,
+    /// Old paths of the article, redirected to it. Normalized with
+    /// [`normalize_path`].
+    pub aliases: Vec<String>,
+    /// Slugs of the listed articles most similar to this one, best first.
+    /// Computed by [`ArticlesRepo::reload`].
+    pub related: Vec<String>,
+    /// Slugs of the articles this one links to with wiki links.
+    pub links: Vec<String>,
+    /// Slugs of the articles linking to this one with wiki links, newest
+    /// first. Computed by [`ArticlesRepo::reload`].
+    pub backlinks: Vec<String>,
+    pub toc: Vec<TocEntry>,
+    /// The markdown body, with its wiki links resolved.
+    body: String,
+    pub word_count: usize,
+    /// Estimated reading time, in minutes.
+    pub reading_time: usize,
 }
 
+/// A group of articles meant to be read in order.
+#[derive(Clone, Debug)]
+pub struct Series {
+    pub name: String,
+    pub slug: String,
+    /// Position of the article in the series. Articles without it come
+    /// after the others, by date.
+    pub order: Option<i64>,
+}
+
+impl Article {
+    /// Loads an article, `titles` being the titles of all the articles by
+    /// slug, for its wiki links.
+    async fn from_markdown(
+        md: MarkdownFile,
+        components: &ComponentRegistry,
+        titles: &HashMap<String, String>,
+    ) -> Result<Self, LoadError> {
+        let title = md.frontmatter["title"]
+            .as_str()
+            .ok_or_else(|| LoadError::new(&md.path, "missing `title`"))?
+            .to_string();
+        let datetime_str = md.frontmatter["datetime"]
+            .as_str()
+            .ok_or_else(|| LoadError::new(&md.path, "missing `datetime`"))?;
+        let datetime = DateTime::parse_from_rfc3339(datetime_str)
+            .map_err(|err| LoadError::new(&md.path, format!("invalid `datetime`: {err}")))?;
+        let publish_at = match md.frontmatter["publish_at"].as_str() {
+            Some(s) => DateTime::parse_from_rfc3339(s)
+                .map_err(|err| LoadError::new(&md.path, format!("invalid `publish_at`: {err}")))?,
+            None => datetime,
+        };
+        let unlisted = md.frontmatter["unlisted"].as_bool().unwrap_or(false);
+        let tags = md.frontmatter["tags"]
+            .as_vec()
+            .map(|tags| {
+                tags.iter()
+                    .filter_map(|t| t.as_str())
+                    .map(normalize_tag)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let series = md.frontmatter["series"].as_str().map(|name| Series {
+            name: name.to_string(),
+            slug: slugify(name),
+            order: md.frontmatter["series_order"].as_i64(),
+        });
+        let aliases = md.frontmatter["aliases"]
+            .as_vec()
+            .map(|aliases| {
+                aliases
+                    .iter()
+                    .filter_map(|a| a.as_str())
+                    .map(normalize_path)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut links = wiki_links::find(&md.content)
+            .into_iter()
+            .map(|link| link.slug)
+            .filter(|slug| titles.contains_key(slug))
+            .collect::<Vec<_>>();
+        links.sort();
+        links.dedup();
+
+        let body = wiki_links::resolve(&md.content, titles);
+        let content = render_markdown(body.clone(), components.clone()).await;
+        let word_count = count_words(&content);
+
+        Ok(Self {
+            title,
+            datetime,
+            publish_at,
+            slug: md.name,
+            source: md.source,
+            toc: table_of_contents(&content),
+            content,
+            unlisted,
+            tags,
+            series,
+            aliases,
+            related: vec![],
+            links,
+            backlinks: vec![],
+            body,
+            word_count,
+            reading_time: word_count.div_ceil(WORDS_PER_MINUTE).max(1),
+        })
+    }
+
+    /// The markdown of the article, without its frontmatter and with its
+    /// wiki links resolved to markdown links.
+    #[must_use]
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+
+    #[must_use]
+    pub fn is_long(&self) -> bool {
+        self.word_count >= LONG_ARTICLE_WORDS && !self.toc.is_empty()
+    }
+
+    /// Scheduled articles are hidden until their `publish_at` time.
+    #[must_use]
+    pub fn is_published(&self) -> bool {
+        self.publish_at <= Utc::now()
+    }
+}
+
+fn count_words(html: &str) -> usize {
+    Html::parse_fragment(html)
+        .root_element()
+        .text()
+        .flat_map(str::split_whitespace)
+        .filter(|w| w.chars().any(char::is_alphanumeric))
+        .count()
+}
+
+/// Ranks, for each article, the listed articles by how similar their text
+/// is, using the cosine similarity of their TF-IDF vectors.
+// article counts are far from the 52 bits of an `f64` mantissa
+#[allow(clippy::cast_precision_loss)]
+fn related_articles(articles: &[Article]) -> Vec<Vec<String>> {
+    let term_counts = articles
+        .iter()
+        .map(|a| {
+            let text = format!("{} {}", a.title, html_to_text(&a.content));
+            let mut counts: HashMap<String, f64> = HashMap::new();
+            for (_, term) in tokenize(&text) {
+                // short words are mostly noise
+                if term.chars().count() >= 3 {
+                    *counts.entry(term).or_default() += 1.0;
+                }
+            }
+            counts
+        })
+        .collect::<Vec<_>>();
+
+    let mut document_frequency: HashMap<&str, f64> = HashMap::new();
+    for counts in &term_counts {
+        for term in counts.keys() {
+            *document_frequency.entry(term).or_default() += 1.0;
+        }
+    }
+
+    let n = articles.len() as f64;
+    let vectors = term_counts
+        .iter()
+        .map(|counts| {
+            let mut vector = counts
+                .iter()
+                .map(|(term, count)| {
+                    (
+                        term.as_str(),
+                        count * (n / document_frequency[term.as_str()]).ln(),
+                    )
+                })
+                .collect::<HashMap<_, _>>();
+            let norm = vector.values().map(|w| w * w).sum::<f64>().sqrt();
+            if norm > 0.0 {
+                for w in vector.values_mut() {
+                    *w /= norm;
+                }
+            }
+            vector
+        })
+        .collect::<Vec<_>>();
+
+    vectors
+        .iter()
+        .enumerate()
+        .map(|(i, vector)| {
+            let mut scores = vectors
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i && !articles[*j].unlisted)
+                .map(|(j, other)| {
+                    let score = vector
+                        .iter()
+                        .filter_map(|(term, w)| other.get(term).map(|o| w * o))
+                        .sum::<f64>();
+                    (j, score)
+                })
+                .filter(|(_, score)| *score > 0.0)
+                .collect::<Vec<_>>();
+            scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+            scores
+                .into_iter()
+                .map(|(j, _)| articles[j].slug.clone())
+                .collect()
+        })
+        .collect()
+}
+
+/// Inverts the wiki links of the articles: for each slug, the articles
+/// linking to it, in the order of `articles`.
+fn backlinks(articles: &[Article]) -> HashMap<String, Vec<String>> {
+    let mut backlinks: HashMap<String, Vec<String>> = HashMap::new();
+    for article in articles {
+        for slug in article.links.iter().filter(|slug| **slug != article.slug) {
+            backlinks
+                .entry(slug.clone())
+                .or_default()
+                .push(article.slug.clone());
+        }
+    }
+    backlinks
+}
+
+/// Returns the titles of the articles that wiki links can point to, by
+/// slug: the published, listed ones.
+#[must_use]
+pub fn linkable_titles(files: &[MarkdownFile]) -> HashMap<String, String> {
+    let now = Utc::now();
+    files
+        .iter()
+        .filter(|md| !md.frontmatter["unlisted"].as_bool().unwrap_or(false))
+        .filter(|md| {
+            md.frontmatter["publish_at"]
+                .as_str()
+                .or_else(|| md.frontmatter["datetime"].as_str())
+                .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+                .is_some_and(|publish_at| publish_at <= now)
+        })
+        .map(|md| {
+            let title = md.frontmatter["title"].as_str().unwrap_or(&md.name);
+            (md.name.clone(), title.to_string())
+        })
+        .collect()
+}
+
+/// Articles loaded from `./articles`.
+///
+/// Cloning the repo is cheap: every clone shares the same set of articles,
+/// which gets swapped atomically by [`ArticlesRepo::reload`].
 #[derive(Clone, Debug)]
 pub struct ArticlesRepo {
-    pub articles: Vec<Article>,
+    articles: Arc<RwLock<Snapshot>>,
+    components: ComponentRegistry,
+}
+
+#[derive(Debug)]
+struct Snapshot {
+    articles: Arc<Vec<Article>>,
+    loaded_at: DateTime<FixedOffset>,
+    /// EPUB books built from these articles, by key.
+    epubs: Arc<Mutex<HashMap<String, Vec<u8>>>>,
 }
 
-impl<'a> ArticlesRepo {
-    pub async fn new() -> Self {
-        let markdown_files = load_dir("./articles");
+impl ArticlesRepo {
+    pub async fn new(components: ComponentRegistry) -> Self {
+        let repo = Self {
+            articles: Arc::new(RwLock::new(Snapshot {
+                articles: Arc::new(vec![]),
+                loaded_at: Utc::now().fixed_offset(),
+                epubs: Arc::default(),
+            })),
+            components,
+        };
+        repo.reload().await;
+        repo
+    }
+
+    /// Parses and renders every article again, then swaps the new set in.
+    ///
+    /// A file that fails to load keeps its last good version, if any. If the
+    /// directory itself can't be read, every article does.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a thread panicked while holding the lock on the articles.
+    pub async fn reload(&self) {
+        let loaded = load_dir(ARTICLES_DIR);
+        let dir_error = loaded
+            .iter()
+            .filter_map(|md| md.as_ref().err())
+            .find(|err| err.path == Path::new(ARTICLES_DIR));
+        if let Some(err) = dir_error {
+            tracing::error!("failed to load articles, keeping the previous ones: {err}");
+            return;
+        }
+
+        let mut previous = self
+            .snapshot()
+            .iter()
+            .map(|a| (a.slug.clone(), a.clone()))
+            .collect::<HashMap<_, _>>();
+
         let mut articles = vec![];
-        for md in markdown_files {
-            let title = md.frontmatter["title"].as_str().unwrap().to_string();
-            let datetime_str = md.frontmatter["datetime"].as_str().unwrap();
-            let datetime = DateTime::parse_from_rfc3339(datetime_str).unwrap();
-            let unlisted = md.frontmatter["unlisted"].as_bool().unwrap_or(false);
-            articles.push(Article {
-                title,
-                datetime,
-                slug: md.name.clone(),
-                content: async {
-                    html! {
-                        <Markdown source=md.content />
-                    }
+        let mut files = vec![];
+        for md in loaded {
+            match md {
+                Ok(md) => files.push(md),
+                Err(err) => {
+                    tracing::error!("failed to load article: {err}");
+                    articles.extend(Self::slug_of(&err.path).and_then(|s| previous.remove(&s)));
                 }
-                .await,
-                unlisted,
-            });
+            }
+        }
+
+        // articles that failed to load keep their last title
+        let mut titles = linkable_titles(&files);
+        titles.extend(
+            articles
+                .iter()
+                .filter(|a| a.is_published() && !a.unlisted)
+                .map(|a| (a.slug.clone(), a.title.clone())),
+        );
+        let slugs = files
+            .iter()
+            .map(|md| md.name.clone())
+            .chain(articles.iter().map(|a| a.slug.clone()))
+            .collect::<HashSet<_>>();
+
+        for md in files {
+            let slug = md.name.clone();
+            let problems = check_file(&md, &self.components).await;
+            if !problems.is_empty() {
+                for problem in problems {
+                    tracing::error!("invalid article: {problem}");
+                }
+                articles.extend(previous.remove(&slug));
+                continue;
+            }
+            // unresolved links are rendered as text, they don't make the
+            // article invalid
+            let links = check_links(&md, &titles, &slugs);
+            for problem in links.errors {
+                tracing::error!("invalid article: {problem}");
+            }
+            for warning in links.warnings {
+                tracing::warn!("{warning}");
+            }
+
+            match Article::from_markdown(md, &self.components, &titles).await {
+                Ok(article) => articles.push(article),
+                Err(err) => {
+                    tracing::error!("failed to load article: {err}");
+                    articles.extend(previous.remove(&slug));
+                }
+            }
         }
         articles.sort_by(|a, b| b.datetime.cmp(&a.datetime));
+        let related = related_articles(&articles);
+        for (article, related) in articles.iter_mut().zip(related) {
+            article.related = related;
+        }
+        let backlinks = backlinks(&articles);
+        for article in &mut articles {
+            article.backlinks = backlinks.get(&article.slug).cloned().unwrap_or_default();
+        }
+
+        *self.articles.write().unwrap() = Snapshot {
+            articles: Arc::new(articles),
+            loaded_at: Utc::now().fixed_offset(),
+            epubs: Arc::default(),
+        };
+    }
+
+    /// Watches `./articles` and reloads the repo whenever something changes
+    /// in there, or when a scheduled article gets published, so that the
+    /// links to it get resolved. `on_change` is called after each reload.
+    pub fn watch(&self, on_change: impl Fn() + Send + 'static) {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let debouncer = new_debouncer(
+            Duration::from_millis(200),
+            move |res: DebounceEventResult| match res {
+                Ok(_) => {
+                    let _ = tx.send(());
+                }
+                Err(err) => tracing::error!("error watching articles: {err}"),
+            },
+        );
+        let mut debouncer = match debouncer {
+            Ok(debouncer) => debouncer,
+            Err(err) => {
+                tracing::error!("failed to watch articles: {err}");
+                return;
+            }
+        };
+        if let Err(err) = debouncer
+            .watcher()
+            .watch(Path::new(ARTICLES_DIR), RecursiveMode::NonRecursive)
+        {
+            tracing::error!("failed to watch articles: {err}");
+            return;
+        }
+
+        let repo = self.clone();
+        tokio::spawn(async move {
+            // keep the watcher alive for as long as the task runs
+            let _debouncer = debouncer;
+            loop {
+                let next_publish_at = repo.next_publish_at();
+                let published = async {
+                    match next_publish_at {
+                        Some(at) => {
+                            let wait = (at.with_timezone(&Utc) - Utc::now()).to_std();
+                            tokio::time::sleep(wait.unwrap_or_default()).await;
+                        }
+                        None => std::future::pending().await,
+                    }
+                };
+                tokio::select! {
+                    changed = rx.recv() => {
+                        if changed.is_none() {
+                            break;
+                        }
+                        tracing::info!("articles changed, reloading");
+                    }
+                    () = published => tracing::info!("scheduled article published, reloading"),
+                }
+                repo.reload().await;
+                on_change();
+            }
+        });
+    }
+
+    /// Returns when the next scheduled article gets published, if any.
+    fn next_publish_at(&self) -> Option<DateTime<FixedOffset>> {
+        self.snapshot()
+            .iter()
+            .filter(|a| !a.is_published())
+            .map(|a| a.publish_at)
+            .min()
+    }
+
+    fn snapshot(&self) -> Arc<Vec<Article>> {
+        self.articles.read().unwrap().articles.clone()
+    }
+
+    /// When the articles were last loaded. Anything rendered from them may
+    /// have changed since then, even if their dates didn't.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a thread panicked while holding the lock on the articles.
+    #[must_use]
+    pub fn loaded_at(&self) -> DateTime<FixedOffset> {
+        self.articles.read().unwrap().loaded_at
+    }
+
+    /// The EPUB book cached as `key`, built with `build` if it isn't yet.
+    /// Books are cached until the next reload, so `key` must tell apart
+    /// the books of different sets of articles.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a thread panicked while holding the lock on the articles
+    /// or on the books.
+    pub async fn epub<Fut>(&self, key: &str, build: impl FnOnce() -> Fut) -> Vec<u8>
+    where
+        Fut: Future<Output = Vec<u8>>,
+    {
+        let epubs = self.articles.read().unwrap().epubs.clone();
+        if let Some(book) = epubs.lock().unwrap().get(key) {
+            return book.clone();
+        }
+        // a reload while building drops the book along with the old cache
+        let book = build().await;
+        epubs.lock().unwrap().insert(key.to_string(), book.clone());
+        book
+    }
+
+    /// The MDX components articles are rendered with.
+    #[must_use]
+    pub fn components(&self) -> &ComponentRegistry {
+        &self.components
+    }
+
+    fn slug_of(path: &Path) -> Option<String> {
+        path.file_stem()
+            .and_then(|s| s.to_str())
+            .map(ToString::to_string)
+    }
+
+    pub fn list(&self) -> Vec<Article> {
+        self.list_including_scheduled()
+            .into_iter()
+            .filter(Article::is_published)
+            .collect()
+    }
+
+    /// Like [`ArticlesRepo::list`], but also returns the articles that are
+    /// not published yet.
+    #[must_use]
+    pub fn list_including_scheduled(&self) -> Vec<Article> {
+        self.snapshot()
+            .iter()
+            .filter(|a| !a.unlisted)
+            .cloned()
+            .collect()
+    }
+
+    #[must_use]
+    pub fn list_by_tag(&self, tag: &str) -> Vec<Article> {
+        self.list()
+            .into_iter()
+            .filter(|a| a.tags.iter().any(|t| t == tag))
+            .collect()
+    }
+
+    /// Returns the articles of a series, in reading order.
+    #[must_use]
+    pub fn list_series(&self, slug: &str) -> Vec<Article> {
+        let mut articles = self
+            .list()
+            .into_iter()
+            .filter(|a| a.series.as_ref().is_some_and(|s| s.slug == slug))
+            .collect::<Vec<_>>();
+        articles.sort_by_key(|a| {
+            let order = a.series.as_ref().and_then(|s| s.order);
+            (order.is_none(), order, a.datetime)
+        });
+        articles
+    }
+
+    /// Returns the published articles most similar to `article`.
+    #[must_use]
+    pub fn related(&self, article: &Article) -> Vec<Article> {
+        let articles = self.snapshot();
+        article
+            .related
+            .iter()
+            .filter_map(|slug| articles.iter().find(|a| &a.slug == slug))
+            .filter(|a| a.is_published())
+            .take(RELATED_ARTICLES)
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the published, listed articles linking to `article`.
+    #[must_use]
+    pub fn backlinks(&self, article: &Article) -> Vec<Article> {
+        let articles = self.snapshot();
+        article
+            .backlinks
+            .iter()
+            .filter_map(|slug| articles.iter().find(|a| &a.slug == slug))
+            .filter(|a| a.is_published() && !a.unlisted)
+            .cloned()
+            .collect()
+    }
 
-        Self { articles }
+    /// Returns the published article, listed or not, that used to live at
+    /// `path`, according to its `aliases`.
+    #[must_use]
+    pub fn get_article_by_alias(&self, path: &str) -> Option<Article> {
+        let path = normalize_path(path);
+        self.snapshot()
+            .iter()
+            .filter(|a| a.is_published())
+            .find(|a| a.aliases.contains(&path))
+            .cloned()
     }
 
-    pub fn list(&'a self) -> Vec<&'a Article> {
-        self.articles.iter().filter(|a| !a.unlisted).collect()
+    pub fn get_article_by_slug(&self, slug: &str) -> Option<Article> {
+        self.get_scheduled_article_by_slug(slug)
+            .filter(Article::is_published)
     }
 
-    pub fn get_article_by_slug(&self, slug: &str) -> Option<&Article> {
-        self.articles.iter().find(|p| p.slug == slug)
+    /// Like [`ArticlesRepo::get_article_by_slug`], but also returns articles
+    /// that are not published yet. Meant for admins previewing them.
+    #[must_use]
+    pub fn get_scheduled_article_by_slug(&self, slug: &str) -> Option<Article> {
+        self.snapshot().iter().find(|p| p.slug == slug).cloned()
     }
 }
@@ -13,6 +13,7 @@ pub struct BookmarkRow {
     pub image: Option<String>,
     pub posted_at: chrono::DateTime<chrono::Utc>,
     pub created_at: chrono::DateTime<chrono::Utc>,
+    pub tags: Vec<String>,
 }
 
 #[derive(Debug)]
@@ -26,6 +27,7 @@ pub struct Bookmark {
     pub image: Option<String>,
     pub posted_at: chrono::DateTime<chrono::Utc>,
     pub created_at: chrono::DateTime<chrono::Utc>,
+    pub tags: Vec<String>,
 }
 
 impl Bookmark {
@@ -45,10 +47,23 @@ impl Bookmark {
             image: row.image,
             posted_at: row.posted_at,
             created_at: row.created_at,
+            tags: row.tags,
         }
     }
 }
 
+/// A bookmark to add with [`BookmarksRepo::add`].
+pub struct NewBookmark<'a> {
+    pub slug: &'a str,
+    pub url: &'a str,
+    pub title: &'a str,
+    pub description: &'a str,
+    pub favicon: Option<&'a str>,
+    pub image: Option<&'a str>,
+    pub posted_at: DateTime<chrono::Utc>,
+    pub tags: &'a [String],
+}
+
 #[derive(Clone)]
 pub struct BookmarksRepo {
     pool: PgPool,
@@ -78,46 +93,90 @@ impl BookmarksRepo {
         .await
     }
 
-    pub async fn get_by_slug(&self, slug: &str) -> Option<Bookmark> {
+    /// Lists the bookmarks tagged with `tag`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the database can't be queried.
+    pub async fn list_by_tag(&self, tag: &str) -> Vec<Bookmark> {
         let mut conn = self.pool.acquire().await.unwrap();
         let res = sqlx::query_as::<_, BookmarkRow>(
-            r#"
+            r"
+            select * from bookmarks
+            where $1 = any(tags)
+            order by created_at desc
+        ",
+        )
+        .bind(tag)
+        .fetch_all(&mut conn)
+        .await
+        .unwrap();
+
+        join_all(
+            res.into_iter()
+                .map(|row| async { Bookmark::from_row(row).await }),
+        )
+        .await
+    }
+
+    /// Returns the bookmark with `slug`, if any.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the database can't be queried.
+    pub async fn get_by_slug(&self, slug: &str) -> Option<Bookmark> {
+        self.try_get_by_slug(slug).await.unwrap()
+    }
+
+    /// Like [`BookmarksRepo::get_by_slug`], but returns database errors instead
+    /// of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database can't be queried.
+    pub async fn try_get_by_slug(&self, slug: &str) -> Result<Option<Bookmark>, sqlx::Error> {
+        let mut conn = self.pool.acquire().await?;
+        let res = sqlx::query_as::<_, BookmarkRow>(
+            r"
             select * from bookmarks
             where slug = $1
-        "#,
+        ",
         )
         .bind(slug)
         .fetch_optional(&mut conn)
-        .await
-        .unwrap();
-        Bookmark::from_row(res?).await.into()
+        .await?;
+        Ok(match res {
+            Some(row) => Some(Bookmark::from_row(row).await),
+            None => None,
+        })
     }
 
-    pub async fn add(
-        &self,
-        slug: &str,
-        url: &str,
-        title: &str,
-        description: &str,
-        favicon: Option<&str>,
-        image: Option<&str>,
-        posted_at: &DateTime<chrono::Utc>,
-    ) -> Result<PgQueryResult, sqlx::Error> {
+    /// Stores a new bookmark.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the insert fails, e.g. because the slug is taken.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no connection to the database can be acquired.
+    pub async fn add(&self, bookmark: &NewBookmark<'_>) -> Result<PgQueryResult, sqlx::Error> {
         let mut conn = self.pool.acquire().await.unwrap();
         conn.execute(
             sqlx::query(
-                r#"
-            insert into bookmarks (slug, url, title, description, favicon, image, posted_at)
-            values ($1, $2, $3, $4, $5, $6, $7)
-            "#,
+                r"
+            insert into bookmarks (slug, url, title, description, favicon, image, posted_at, tags)
+            values ($1, $2, $3, $4, $5, $6, $7, $8)
+            ",
             )
-            .bind(slug)
-            .bind(url)
-            .bind(title)
-            .bind(description)
-            .bind(favicon)
-            .bind(image)
-            .bind(posted_at),
+            .bind(bookmark.slug)
+            .bind(bookmark.url)
+            .bind(bookmark.title)
+            .bind(bookmark.description)
+            .bind(bookmark.favicon)
+            .bind(bookmark.image)
+            .bind(bookmark.posted_at)
+            .bind(bookmark.tags),
         )
         .await
     }
@@ -0,0 +1,114 @@
+use std::{collections::HashMap, error::Error, fs, sync::Arc};
+
+use serde::Deserialize;
+
+use crate::images::{search_available_sources, ImageSrc};
+
+/// The characters of the `Dialog` component, with their avatars.
+pub const CHARACTERS_FILE: &str = "./static/characters/characters.json";
+
+/// Background of the dialogs on the left, unless the character has its own.
+const DEFAULT_LEFT: &str = "#ffd787";
+/// Background of the dialogs on the right, unless the character has its own.
+const DEFAULT_RIGHT: &str = "#abe5bc";
+
+/// A character as listed in [`CHARACTERS_FILE`]: its avatar is either an
+/// image, whose other formats next to it are used too, or an SVG file.
+#[derive(Deserialize)]
+struct CharacterEntry {
+    name: String,
+    image: Option<String>,
+    svg: Option<String>,
+    alt: Option<String>,
+    left: Option<String>,
+    right: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Character {
+    pub name: String,
+    pub avatar: Avatar,
+    /// CSS colours of the dialogs of the character, on either side.
+    pub left: String,
+    pub right: String,
+}
+
+#[derive(Clone, Debug)]
+pub enum Avatar {
+    Image {
+        sources: Vec<ImageSrc>,
+        alt: String,
+    },
+    /// The markup of an SVG file, inlined in the page.
+    Svg(String),
+}
+
+/// The characters that can speak in dialogs, by name.
+///
+/// Cloning is cheap: every clone shares the same characters.
+#[derive(Clone, Debug, Default)]
+pub struct Characters(Arc<HashMap<String, Character>>);
+
+impl Characters {
+    /// Loads the characters of [`CHARACTERS_FILE`] and their avatars.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be read or parsed, or if the
+    /// avatar of a character can't be found.
+    pub fn load() -> Result<Self, Box<dyn Error>> {
+        let file = fs::read_to_string(CHARACTERS_FILE)
+            .map_err(|err| format!("{CHARACTERS_FILE}: {err}"))?;
+        let entries = serde_json::from_str::<Vec<CharacterEntry>>(&file)
+            .map_err(|err| format!("{CHARACTERS_FILE}: {err}"))?;
+
+        let mut characters = HashMap::new();
+        for entry in entries {
+            let character =
+                Character::from_entry(entry).map_err(|err| format!("{CHARACTERS_FILE}: {err}"))?;
+            if characters.contains_key(&character.name) {
+                return Err(format!(
+                    "{CHARACTERS_FILE}: duplicate character `{}`",
+                    character.name
+                )
+                .into());
+            }
+            characters.insert(character.name.clone(), character);
+        }
+        Ok(Self(Arc::new(characters)))
+    }
+
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&Character> {
+        self.0.get(name)
+    }
+}
+
+impl Character {
+    fn from_entry(entry: CharacterEntry) -> Result<Self, String> {
+        let name = entry.name;
+        let avatar = match (entry.image, entry.svg) {
+            (Some(image), None) => {
+                let sources = search_available_sources(&image);
+                if sources.is_empty() {
+                    return Err(format!("no image found for `{name}` at {image}"));
+                }
+                Avatar::Image {
+                    sources,
+                    alt: entry.alt.unwrap_or_else(|| name.clone()),
+                }
+            }
+            (None, Some(svg)) => Avatar::Svg(
+                fs::read_to_string(&svg).map_err(|err| format!("`{name}`: {svg}: {err}"))?,
+            ),
+            _ => return Err(format!("`{name}` must have either an `image` or an `svg`")),
+        };
+
+        Ok(Self {
+            avatar,
+            left: entry.left.unwrap_or_else(|| DEFAULT_LEFT.to_string()),
+            right: entry.right.unwrap_or_else(|| DEFAULT_RIGHT.to_string()),
+            name,
+        })
+    }
+}
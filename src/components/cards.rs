@@ -0,0 +1,166 @@
+use reqwest::Url;
+use rscx::{component, html, html_escape::encode_text, props};
+
+use crate::{
+    apps::{App, AppsRepo},
+    bookmarks::{Bookmark, BookmarksRepo},
+    images::Image,
+    mdx::{Component, ComponentRegistry, Props, RenderError, Text},
+};
+
+/// Registers `BookmarkCard` and `AppCard`, which embed a bookmark or an app
+/// by slug, looked up when the markdown is rendered.
+///
+/// App descriptions are markdown too: `apps` should be given a registry
+/// without the cards, so that an app can't end up embedding itself.
+pub fn register(registry: &mut ComponentRegistry, bookmarks: BookmarksRepo, apps: AppsRepo) {
+    let repo = bookmarks.clone();
+    registry.register(
+        Component::new("BookmarkCard", move |props| {
+            let repo = repo.clone();
+            async move {
+                let bookmark = find_bookmark(repo, &props).await?;
+                Ok(html! { <BookmarkCard bookmark=bookmark /> })
+            }
+        })
+        .required::<String>("slug")
+        .text(|props| {
+            let slug = props.get::<String>("slug").unwrap_or_default();
+            Text::Link {
+                url: format!("/bookmarks/{slug}"),
+                label: String::new(),
+            }
+        })
+        .validate(move |props| {
+            let repo = bookmarks.clone();
+            async move {
+                find_bookmark(repo, &props)
+                    .await
+                    .map(|_| ())
+                    .map_err(|err| err.0)
+            }
+        }),
+    );
+
+    let repo = apps.clone();
+    registry.register(
+        Component::new("AppCard", move |props| {
+            let repo = repo.clone();
+            async move {
+                let app = find_app(repo, &props).await?;
+                Ok(html! { <AppCard app=app /> })
+            }
+        })
+        .required::<String>("slug")
+        .text(|props| {
+            let slug = props.get::<String>("slug").unwrap_or_default();
+            Text::Link {
+                url: format!("/uses/{slug}"),
+                label: String::new(),
+            }
+        })
+        .validate(move |props| {
+            let repo = apps.clone();
+            async move {
+                find_app(repo, &props)
+                    .await
+                    .map(|_| ())
+                    .map_err(|err| err.0)
+            }
+        }),
+    );
+}
+
+async fn find_bookmark(repo: BookmarksRepo, props: &Props) -> Result<Bookmark, RenderError> {
+    let slug = props.get::<String>("slug")?;
+    // components must render with futures that are `Sync`, which the ones
+    // of the database driver aren't: the query runs on its own task
+    tokio::spawn(async move { repo.try_get_by_slug(&slug).await })
+        .await
+        .map_err(|err| RenderError(format!("failed to get bookmark: {err}")))?
+        .map_err(|err| RenderError(format!("failed to get bookmark: {err}")))?
+        .ok_or_else(|| not_found("bookmark", props))
+}
+
+async fn find_app(repo: AppsRepo, props: &Props) -> Result<App, RenderError> {
+    let slug = props.get::<String>("slug")?;
+    tokio::spawn(async move { repo.try_get_by_slug(&slug).await })
+        .await
+        .map_err(|err| RenderError(format!("failed to get app: {err}")))?
+        .map_err(|err| RenderError(format!("failed to get app: {err}")))?
+        .ok_or_else(|| not_found("app", props))
+}
+
+fn not_found(kind: &str, props: &Props) -> RenderError {
+    let slug = props.get::<String>("slug").unwrap_or_default();
+    RenderError(format!("no {kind} with slug `{slug}`"))
+}
+
+#[props]
+pub struct BookmarkCardProps {
+    bookmark: Bookmark,
+}
+
+#[component]
+pub fn BookmarkCard(props: BookmarkCardProps) -> String {
+    let href = format!("/bookmarks/{}", props.bookmark.slug);
+    let title = encode_text(&props.bookmark.title).into_owned();
+
+    html! {
+        <a href=href class="card block mt-6 border border-black rounded-md shadow-neu-1 bg-white overflow-hidden">
+            {
+                props.bookmark.image.map(|src| html! {
+                    <img src=src alt="" class="block w-full" />
+                }).unwrap_or_default()
+            }
+            <div class="flex flex-col gap-1 px-4 py-2">
+                <span class="font-semibold">{title}</span>
+                <span class="flex flex-row opacity-60 items-center text-sm">
+                    {
+                        props.bookmark.favicon.map_or(
+                            html! {
+                                <span class="block rounded-sm bg-gray-500 w-4 h-4 mr-1 shrink-0" />
+                            },
+                            |favicon| html! {
+                                <img src=favicon alt="Favicon" class="block rounded-sm w-4 h-4 mr-1" />
+                            },
+                        )
+                    }
+                    {&props.bookmark.hostname}
+                </span>
+            </div>
+        </a>
+    }
+}
+
+#[props]
+pub struct AppCardProps {
+    app: App,
+}
+
+#[component]
+pub async fn AppCard(props: AppCardProps) -> String {
+    let href = format!("/uses/{}", props.app.slug);
+    let name = encode_text(&props.app.name).into_owned();
+    let hostname = Url::parse(&props.app.url)
+        .ok()
+        .and_then(|url| url.host_str().map(ToString::to_string))
+        .unwrap_or_default();
+    let icon = if props.app.images.is_empty() {
+        html! { <span class="block rounded-lg bg-gray-500 w-10 h-10 shrink-0" /> }
+    } else {
+        html! {
+            <Image sources=props.app.images alt=props.app.name class="w-10 h-10 rounded-lg bg-white object-contain shrink-0".into() />
+        }
+    };
+
+    html! {
+        <a href=href class="card flex flex-row gap-4 items-center mt-6 border border-black px-4 py-2 rounded-md shadow-neu-1 bg-white">
+            {icon}
+            <span class="flex flex-col gap-1 min-w-0">
+                <span class="font-semibold">{name}</span>
+                <span class="opacity-60 text-sm">{hostname}</span>
+            </span>
+        </a>
+    }
+}
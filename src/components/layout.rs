@@ -6,8 +6,10 @@ use rscx::{component, context::expect_context, html, props, CollectFragmentAsync
 use stylist::style;
 
 use crate::{
+    components::search::SearchBox,
+    export::is_exporting,
     hash,
-    icons::{App, Bookmark, Burger, Heart, Home, Logout, Notebook, SmallX},
+    icons::{App, Bookmark, Burger, Heart, Home, Logout, Notebook, SmallX, Tag},
     images::StaticImg,
     meta::{Dedup, MetaContextRender},
     pages::auth::AuthContext,
@@ -53,6 +55,7 @@ pub async fn Layout(props: LayoutProps) -> String {
                 <link rel="preload" href="/static/ClashDisplay-Variable.woff2" crossorigin="anonymous" as_="font" type="font/woff2" />
                 <CssFile path="style.dist.css" />
                 <CssFile path="tailwind.css" />
+                <link rel="alternate" type="application/atom+xml" title="Everything on anto.pt" href="/feed.xml" />
                 <script defer=true data-domain="anto.pt" src="https://plausible.anto.pt/js/plausible.js"></script>
                 <MetaContextRender />
                 {props.head}
@@ -112,6 +115,7 @@ pub async fn MobileNavbar() -> String {
                 <div class="sticky bottom-0 top-0 max-h-screen overflow-auto p-4 lg:border-r-2 w-full space-y-20 border-t-2 border-black bg-lightviolet bg-pattern-hideout pb-10">
                     <div class="space-y-8">
                         <SidebarHeader img_src=Some("static/bulb.webp".into()) title="Antonio Pitasi".into() />
+                        <SearchBox />
                         <RootSidebarNav />
                     </div>
                     <LoginWidget />
@@ -131,7 +135,9 @@ pub fn LoginWidget() -> String {
     let uri = expect_context::<http::Uri>();
     let auth = expect_context::<AuthContext>();
 
-    if let Some(user) = &auth.current_user {
+    if is_exporting() {
+        String::new()
+    } else if let Some(user) = &auth.current_user {
         let pic = user
             .picture
             .clone()
@@ -182,6 +188,7 @@ pub fn RootSidebar() -> String {
         ">
             <div class="space-y-16">
                 <SidebarHeader title="Antonio Pitasi".into() img_src=Some("static/bulb.webp".into()) />
+                <SearchBox />
                 <RootSidebarNav />
             </div>
             <LoginWidget />
@@ -220,6 +227,7 @@ pub fn RootSidebarNav() -> String {
         ("/articles", "Articles", Some(html! { <Notebook /> })),
         ("/bookmarks", "Bookmarks", Some(html! { <Bookmark /> })),
         ("/uses", "Uses", Some(html! { <App /> })),
+        ("/tags", "Tags", Some(html! { <Tag /> })),
     ];
 
     let nav_items = items
@@ -400,12 +408,20 @@ pub struct HeaderProps {
 #[component]
 pub fn Header(props: HeaderProps) -> String {
     let uri = expect_context::<http::Uri>();
+    // likes need the server, static exports go without them
+    let like_button = if is_exporting() {
+        String::new()
+    } else {
+        html! {
+            <LazyHeartButton path=format!("/components/like-btn?url={}", uri.path()) />
+        }
+    };
     html! {
         <header class="sticky top-0 z-10 flex w-full items-center justify-between gap-2 overflow-hidden border-b-2 border-black bg-yellow px-3 py-3 lg:justify-end lg:gap-4">
             <span id="header-title" class="line-clamp-1 text-ellipsis font-bold" style="opacity: 0; transform: translateY(30px) translateZ(0px);">
                 {props.title}
             </span>
-            <LazyHeartButton path=format!("/components/like-btn?url={}", uri.path()) />
+            {like_button}
         </header>
         <script>{r#"
             var animation = anime({
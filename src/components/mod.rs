@@ -1,3 +1,6 @@
+pub mod cards;
 pub mod heart;
 pub mod layout;
 pub mod md;
+pub mod search;
+pub mod tags;
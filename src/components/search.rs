@@ -0,0 +1,96 @@
+use axum::{extract::Query, response::IntoResponse, Extension};
+use rscx::{component, html, props, CollectFragment};
+use serde::Deserialize;
+
+use crate::{export::is_exporting, meta::render_with_meta, search::SearchIndex};
+
+/// Maximum number of results shown while typing in the search box.
+const LIVE_RESULTS: usize = 5;
+
+#[derive(Deserialize)]
+pub struct SearchQuery {
+    #[serde(default)]
+    pub q: String,
+}
+
+#[component]
+pub fn SearchBox() -> String {
+    // searching needs the server, static exports go without it
+    if is_exporting() {
+        return String::new();
+    }
+
+    html! {
+        <form action="/search" method="get" class="space-y-2">
+            <input
+                type="search"
+                name="q"
+                placeholder="Search..."
+                autocomplete="off"
+                class="w-full rounded-md border-2 border-black bg-white px-3 py-2 text-sm shadow-neu-1 focus:outline-none"
+                hx-get="/components/search"
+                hx-trigger="input changed delay:300ms, search"
+                hx-target="next .search-results"
+                hx-swap="innerHTML"
+            />
+            <div class="search-results empty:hidden rounded-md border-2 border-black bg-white text-sm"></div>
+        </form>
+    }
+}
+
+pub async fn handler_get(
+    Extension(search_index): Extension<SearchIndex>,
+    Query(SearchQuery { q }): Query<SearchQuery>,
+) -> impl IntoResponse {
+    render_with_meta(
+        || {},
+        || async move {
+            if q.trim().is_empty() {
+                return String::new();
+            }
+            html! {
+                <SearchResults index=search_index query=q limit=LIVE_RESULTS compact=true />
+            }
+        },
+    )
+    .await
+}
+
+#[props]
+pub struct SearchResultsProps {
+    index: SearchIndex,
+    query: String,
+    limit: usize,
+    #[builder(default)]
+    compact: bool,
+}
+
+#[component]
+pub fn SearchResults(props: SearchResultsProps) -> String {
+    let results = props.index.search(&props.query, props.limit);
+    if results.is_empty() {
+        return html! {
+            <p class="px-3 py-2 opacity-60">"No results."</p>
+        };
+    }
+
+    results
+        .into_iter()
+        .map(|result| {
+            let snippet = if props.compact {
+                String::new()
+            } else {
+                html! {
+                    <p class="opacity-80">{result.snippet}</p>
+                }
+            };
+            html! {
+                <a href=result.document.url class="block px-3 py-2 hover:bg-yellow">
+                    <span class="block text-xs uppercase opacity-60">{result.document.kind.label()}</span>
+                    <span class="block font-semibold">{result.title}</span>
+                    {snippet}
+                </a>
+            }
+        })
+        .collect_fragment()
+}
@@ -0,0 +1,33 @@
+use rscx::{component, html, html_escape::encode_text, props, CollectFragment};
+
+use crate::tags::tag_path;
+
+#[props]
+pub struct TagListProps {
+    tags: Vec<String>,
+}
+
+#[component]
+pub fn TagList(props: TagListProps) -> String {
+    if props.tags.is_empty() {
+        return String::new();
+    }
+
+    let tags = props
+        .tags
+        .into_iter()
+        .map(|tag| {
+            html! {
+                <a href=tag_path(&tag) class="rounded-md border border-black bg-white px-2 py-0.5 text-sm shadow-neu-1 hover:bg-yellow">
+                    "#"{encode_text(&tag)}
+                </a>
+            }
+        })
+        .collect_fragment();
+
+    html! {
+        <div class="flex flex-row flex-wrap gap-2">
+            {tags}
+        </div>
+    }
+}
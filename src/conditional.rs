@@ -0,0 +1,96 @@
+use axum::{
+    http::{
+        header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED},
+        HeaderMap, HeaderValue, StatusCode,
+    },
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, FixedOffset, Utc};
+use data_encoding::HEXLOWER;
+
+use crate::hash;
+
+/// Strong entity tag of a response body, for conditional GETs.
+///
+/// # Panics
+///
+/// Panics if the body can't be hashed.
+#[must_use]
+pub fn etag(body: &[u8]) -> String {
+    let digest = hash::sha256_digest(body).expect("failed to hash response body");
+    format!("\"{}\"", HEXLOWER.encode(digest.as_ref()))
+}
+
+/// Answers with `304 Not Modified` if the request's `If-Modified-Since` is
+/// not older than `last_modified`, so that handlers can skip rendering.
+///
+/// `If-Modified-Since` is ignored when `If-None-Match` is present, as the
+/// latter is more precise.
+pub fn not_modified_since(
+    headers: &HeaderMap,
+    last_modified: DateTime<FixedOffset>,
+) -> Option<Response> {
+    if headers.contains_key(IF_NONE_MATCH) {
+        return None;
+    }
+    let since = headers
+        .get(IF_MODIFIED_SINCE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| DateTime::parse_from_rfc2822(v).ok())?;
+
+    // HTTP dates have a one second precision
+    if last_modified.timestamp() <= since.timestamp() {
+        Some(not_modified(None, Some(last_modified)))
+    } else {
+        None
+    }
+}
+
+/// Adds the validators to `response`, or replaces it with `304 Not
+/// Modified` if the request's `If-None-Match` matches `etag`.
+pub fn conditional(
+    headers: &HeaderMap,
+    etag: &str,
+    last_modified: Option<DateTime<FixedOffset>>,
+    response: impl IntoResponse,
+) -> Response {
+    let matches = headers
+        .get_all(IF_NONE_MATCH)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(|tag| tag.trim().trim_start_matches("W/"))
+        .any(|tag| tag == etag || tag == "*");
+    if matches {
+        return not_modified(Some(etag), last_modified);
+    }
+
+    let mut response = response.into_response();
+    set_validators(response.headers_mut(), Some(etag), last_modified);
+    response
+}
+
+fn not_modified(etag: Option<&str>, last_modified: Option<DateTime<FixedOffset>>) -> Response {
+    let mut response = StatusCode::NOT_MODIFIED.into_response();
+    set_validators(response.headers_mut(), etag, last_modified);
+    response
+}
+
+fn set_validators(
+    headers: &mut HeaderMap,
+    etag: Option<&str>,
+    last_modified: Option<DateTime<FixedOffset>>,
+) {
+    if let Some(etag) = etag.and_then(|v| HeaderValue::from_str(v).ok()) {
+        headers.insert(ETAG, etag);
+    }
+    if let Some(last_modified) = last_modified {
+        let date = last_modified
+            .with_timezone(&Utc)
+            .format("%a, %d %b %Y %H:%M:%S GMT")
+            .to_string();
+        if let Ok(date) = HeaderValue::from_str(&date) {
+            headers.insert(LAST_MODIFIED, date);
+        }
+    }
+}
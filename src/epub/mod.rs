@@ -0,0 +1,296 @@
+mod xhtml;
+mod zip;
+
+use std::{collections::HashMap, fmt::Write, num::NonZeroUsize, sync::Mutex, time::Duration};
+
+use chrono::{DateTime, FixedOffset, Utc};
+use lru::LruCache;
+use once_cell::sync::Lazy;
+use reqwest::Url;
+use rscx::html_escape::{encode_double_quoted_attribute, encode_text};
+
+use crate::{articles::Article, markdown::TocEntry, BASE_URL};
+
+use self::{
+    xhtml::{extension, image_urls, to_xhtml},
+    zip::ZipWriter,
+};
+
+const AUTHOR: &str = "Antonio Pitasi";
+
+const STYLE: &str = include_str!("style.css");
+const FONTS: [(&str, &str, &[u8]); 2] = [
+    (
+        "fonts/Inter.ttf",
+        "font/ttf",
+        include_bytes!("../../static/Inter-VariableFont_slnt,wght.ttf"),
+    ),
+    (
+        "fonts/ClashDisplay.woff2",
+        "font/woff2",
+        include_bytes!("../../static/ClashDisplay-Variable.woff2"),
+    ),
+];
+
+/// Downloaded images, by URL, so that books sharing them don't download
+/// them again.
+static IMAGES: Lazy<Mutex<LruCache<String, Vec<u8>>>> =
+    Lazy::new(|| Mutex::new(LruCache::new(NonZeroUsize::new(100).unwrap())));
+
+/// Packages `articles` as an EPUB 3 book, one chapter each, in the order
+/// given. Images are embedded, along with the site's fonts, and the
+/// navigation document lists the headings of every chapter.
+///
+/// # Panics
+///
+/// Panics if the URL of an article doesn't parse, which no slug can cause.
+pub async fn build(title: &str, identifier: &str, articles: &[Article]) -> Vec<u8> {
+    let chapters = articles
+        .iter()
+        .map(|a| (article_url(a), format!("{}.xhtml", a.slug)))
+        .collect::<HashMap<_, _>>();
+
+    // every image is downloaded once, even if used by multiple chapters
+    let mut images = HashMap::new();
+    let mut resources = vec![];
+    for article in articles {
+        let base = Url::parse(&article_url(article)).unwrap();
+        for url in image_urls(&article.content, &base) {
+            if images.contains_key(&url) {
+                continue;
+            }
+            let Some(media_type) = Url::parse(&url)
+                .ok()
+                .and_then(|url| extension(&url))
+                .and_then(|ext| image_media_type(&ext))
+            else {
+                continue;
+            };
+            let Some(data) = fetch_image(&url).await else {
+                continue;
+            };
+            let path = format!("images/{}.{}", resources.len(), media_type.1);
+            images.insert(url, path.clone());
+            resources.push((path, media_type.0, data));
+        }
+    }
+
+    let mut zip = ZipWriter::default();
+    zip.store("mimetype", b"application/epub+zip");
+    zip.deflate("META-INF/container.xml", CONTAINER.as_bytes());
+    zip.deflate(
+        "OEBPS/content.opf",
+        package(title, identifier, articles, &resources).as_bytes(),
+    );
+    zip.deflate("OEBPS/nav.xhtml", navigation(title, articles).as_bytes());
+    zip.deflate("OEBPS/style.css", STYLE.as_bytes());
+    for (path, _, data) in FONTS {
+        zip.deflate(&format!("OEBPS/{path}"), data);
+    }
+    for article in articles {
+        let base = Url::parse(&article_url(article)).unwrap();
+        let body = to_xhtml(&article.content, &base, &images, &chapters);
+        zip.deflate(
+            &format!("OEBPS/{}.xhtml", article.slug),
+            chapter(article, &body).as_bytes(),
+        );
+    }
+    for (path, _, data) in &resources {
+        zip.deflate(&format!("OEBPS/{path}"), data);
+    }
+    zip.finish()
+}
+
+fn article_url(article: &Article) -> String {
+    format!("{BASE_URL}/articles/{}", article.slug)
+}
+
+/// Media type and file extension of the image formats readers support.
+fn image_media_type(ext: &str) -> Option<(&'static str, &'static str)> {
+    match ext {
+        "png" => Some(("image/png", "png")),
+        "jpg" | "jpeg" => Some(("image/jpeg", "jpg")),
+        "gif" => Some(("image/gif", "gif")),
+        "svg" => Some(("image/svg+xml", "svg")),
+        "webp" => Some(("image/webp", "webp")),
+        _ => None,
+    }
+}
+
+/// Reads an image from `static/`, or downloads it if it's not ours. Images
+/// that can't be fetched are left out of the book.
+async fn fetch_image(url: &str) -> Option<Vec<u8>> {
+    if let Some(path) = url.strip_prefix(&format!("{BASE_URL}/static/")) {
+        return std::fs::read(format!("static/{path}")).ok();
+    }
+    if let Some(data) = IMAGES.lock().unwrap().get(url) {
+        return Some(data.clone());
+    }
+
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .ok()?;
+    let res = client
+        .get(url)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status);
+    let data = match res {
+        Ok(res) => res.bytes().await.ok()?.to_vec(),
+        Err(err) => {
+            tracing::warn!("could not download {url} for an EPUB: {err}");
+            return None;
+        }
+    };
+    IMAGES.lock().unwrap().put(url.to_string(), data.clone());
+    Some(data)
+}
+
+/// `dcterms:modified` requires UTC, without fractions of seconds.
+fn modified(articles: &[Article]) -> String {
+    articles
+        .iter()
+        .map(|a| a.datetime)
+        .max()
+        .map_or_else(Utc::now, |d: DateTime<FixedOffset>| d.with_timezone(&Utc))
+        .format("%Y-%m-%dT%H:%M:%SZ")
+        .to_string()
+}
+
+const CONTAINER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>
+"#;
+
+fn package(
+    title: &str,
+    identifier: &str,
+    articles: &[Article],
+    resources: &[(String, &str, Vec<u8>)],
+) -> String {
+    let mut manifest = vec![
+        r#"<item id="nav" href="nav.xhtml" media-type="application/xhtml+xml" properties="nav"/>"#
+            .to_string(),
+        r#"<item id="style" href="style.css" media-type="text/css"/>"#.to_string(),
+    ];
+    for (i, (path, media_type, _)) in FONTS.iter().enumerate() {
+        manifest.push(format!(
+            r#"<item id="font-{i}" href="{path}" media-type="{media_type}"/>"#
+        ));
+    }
+    for (i, (path, media_type, _)) in resources.iter().enumerate() {
+        manifest.push(format!(
+            r#"<item id="image-{i}" href="{path}" media-type="{media_type}"/>"#
+        ));
+    }
+    let mut spine = vec![];
+    for (i, article) in articles.iter().enumerate() {
+        // inline SVGs must be declared
+        let properties = if article.content.contains("<svg") {
+            r#" properties="svg""#
+        } else {
+            ""
+        };
+        manifest.push(format!(
+            r#"<item id="chapter-{i}" href="{}.xhtml" media-type="application/xhtml+xml"{properties}/>"#,
+            article.slug
+        ));
+        spine.push(format!(r#"<itemref idref="chapter-{i}"/>"#));
+    }
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="id" xml:lang="en">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:identifier id="id">{}</dc:identifier>
+    <dc:title>{}</dc:title>
+    <dc:creator>{AUTHOR}</dc:creator>
+    <dc:language>en</dc:language>
+    <meta property="dcterms:modified">{}</meta>
+  </metadata>
+  <manifest>
+    {}
+  </manifest>
+  <spine>
+    {}
+  </spine>
+</package>
+"#,
+        encode_text(identifier),
+        encode_text(title),
+        modified(articles),
+        manifest.join("\n    "),
+        spine.join("\n    "),
+    )
+}
+
+fn navigation(title: &str, articles: &[Article]) -> String {
+    let items = articles.iter().fold(String::new(), |mut items, article| {
+        let href = format!("{}.xhtml", article.slug);
+        let _ = write!(
+            items,
+            r#"<li><a href="{href}">{}</a>{}</li>"#,
+            encode_text(&article.title),
+            toc(&href, &article.toc)
+        );
+        items
+    });
+
+    xhtml_document(
+        title,
+        &format!(
+            r#"<nav epub:type="toc" id="toc"><h1>{}</h1><ol>{items}</ol></nav>"#,
+            encode_text(title)
+        ),
+    )
+}
+
+fn toc(href: &str, entries: &[TocEntry]) -> String {
+    if entries.is_empty() {
+        return String::new();
+    }
+    let items = entries.iter().fold(String::new(), |mut items, entry| {
+        let _ = write!(
+            items,
+            r#"<li><a href="{href}#{}">{}</a>{}</li>"#,
+            encode_double_quoted_attribute(&entry.id),
+            encode_text(&entry.title),
+            toc(href, &entry.children)
+        );
+        items
+    });
+    format!("<ol>{items}</ol>")
+}
+
+fn chapter(article: &Article, body: &str) -> String {
+    xhtml_document(
+        &article.title,
+        &format!(
+            r#"<article><h1>{}</h1><p class="meta">{} · {} min read</p>{body}</article>"#,
+            encode_text(&article.title),
+            article.datetime.format("%B %d, %Y"),
+            article.reading_time,
+        ),
+    )
+}
+
+fn xhtml_document(title: &str, body: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops" lang="en" xml:lang="en">
+<head>
+<meta charset="UTF-8"/>
+<title>{}</title>
+<link rel="stylesheet" type="text/css" href="style.css"/>
+</head>
+<body>{body}</body>
+</html>
+"#,
+        encode_text(title)
+    )
+}
@@ -0,0 +1,262 @@
+use std::{collections::HashMap, fmt::Write};
+
+use reqwest::Url;
+use rscx::html_escape::{encode_double_quoted_attribute, encode_text};
+use scraper::{ElementRef, Html, Node};
+
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track", "wbr",
+];
+
+/// Elements dropped with their content: they are interactive, or can't be
+/// read offline.
+const DROPPED_ELEMENTS: &[&str] = &[
+    "button", "form", "input", "link", "meta", "noscript", "script", "source", "style", "template",
+];
+
+/// Remote media, replaced by a link to them.
+const REMOTE_ELEMENTS: &[&str] = &["audio", "iframe", "video"];
+
+/// Image formats readers support, by preference.
+const IMAGE_FORMATS: &[&str] = &["png", "jpg", "jpeg", "gif", "svg", "webp"];
+
+/// Returns the image URLs of `html`, resolved against `base`. For pictures
+/// with multiple sources, only the one [`to_xhtml`] will use is returned.
+pub fn image_urls(html: &str, base: &Url) -> Vec<String> {
+    let dom = Html::parse_fragment(html);
+    dom.root_element()
+        .descendants()
+        .filter_map(ElementRef::wrap)
+        .filter(|el| match el.value().name() {
+            "picture" => true,
+            "img" => !el
+                .parent()
+                .and_then(ElementRef::wrap)
+                .is_some_and(|p| p.value().name() == "picture"),
+            _ => false,
+        })
+        .filter_map(|el| image_src(el, base))
+        .collect()
+}
+
+/// Converts rendered HTML to the XHTML of an EPUB content document.
+///
+/// Images are pointed to their copy in the book, according to `images`, or
+/// replaced by their alt text. Links to articles that are chapters of the
+/// book, according to `chapters`, are pointed to them.
+pub fn to_xhtml(
+    html: &str,
+    base: &Url,
+    images: &HashMap<String, String>,
+    chapters: &HashMap<String, String>,
+) -> String {
+    let converter = Converter {
+        base,
+        images,
+        chapters,
+    };
+    let dom = Html::parse_fragment(html);
+    let mut out = String::with_capacity(html.len());
+    converter.children(dom.root_element(), &mut out);
+    out
+}
+
+struct Converter<'a> {
+    base: &'a Url,
+    images: &'a HashMap<String, String>,
+    chapters: &'a HashMap<String, String>,
+}
+
+impl Converter<'_> {
+    fn children(&self, el: ElementRef, out: &mut String) {
+        for node in el.children() {
+            if let Some(child) = ElementRef::wrap(node) {
+                self.element(child, out);
+            } else if let Node::Text(text) = node.value() {
+                out.push_str(&encode_text(&**text));
+            }
+        }
+    }
+
+    fn element(&self, el: ElementRef, out: &mut String) {
+        let name = el.value().name();
+        if DROPPED_ELEMENTS.contains(&name) || el.value().classes().any(|c| c == "heading-anchor") {
+            return;
+        }
+        if REMOTE_ELEMENTS.contains(&name) {
+            if let Some(src) = el.value().attr("src").and_then(|s| self.base.join(s).ok()) {
+                let src = encode_double_quoted_attribute(src.as_str());
+                let _ = write!(out, r#"<a href="{src}">{src}</a>"#);
+            }
+            return;
+        }
+        if name == "picture" || name == "img" {
+            self.image(el, out);
+            return;
+        }
+
+        out.push('<');
+        out.push_str(name);
+        for (attr, value) in el.value().attrs() {
+            if attr.starts_with("hx-")
+                || attr.starts_with("on")
+                || matches!(attr, "loading" | "decoding")
+            {
+                continue;
+            }
+            let value = if name == "a" && attr == "href" {
+                self.link(value)
+            } else {
+                value.to_string()
+            };
+            let _ = write!(
+                out,
+                r#" {attr}="{}""#,
+                encode_double_quoted_attribute(&value)
+            );
+        }
+
+        if VOID_ELEMENTS.contains(&name) {
+            out.push_str("/>");
+            return;
+        }
+        out.push('>');
+        self.children(el, out);
+        let _ = write!(out, "</{name}>");
+    }
+
+    fn image(&self, el: ElementRef, out: &mut String) {
+        let img = if el.value().name() == "img" {
+            Some(el)
+        } else {
+            el.children()
+                .filter_map(ElementRef::wrap)
+                .find(|c| c.value().name() == "img")
+        };
+        let alt = img
+            .and_then(|img| img.value().attr("alt"))
+            .unwrap_or_default();
+
+        match image_src(el, self.base).and_then(|src| self.images.get(&src)) {
+            Some(path) => {
+                let _ = write!(
+                    out,
+                    r#"<img src="{}" alt="{}"/>"#,
+                    encode_double_quoted_attribute(path),
+                    encode_double_quoted_attribute(alt)
+                );
+            }
+            None => out.push_str(&encode_text(alt)),
+        }
+    }
+
+    fn link(&self, href: &str) -> String {
+        if href.starts_with('#') {
+            return href.to_string();
+        }
+        let Ok(mut url) = self.base.join(href) else {
+            return href.to_string();
+        };
+        let fragment = url.fragment().map(str::to_string);
+        url.set_fragment(None);
+        match (self.chapters.get(url.as_str()), fragment) {
+            (Some(chapter), Some(fragment)) => format!("{chapter}#{fragment}"),
+            (Some(chapter), None) => chapter.clone(),
+            (None, Some(fragment)) => {
+                url.set_fragment(Some(&fragment));
+                url.to_string()
+            }
+            (None, None) => url.to_string(),
+        }
+    }
+}
+
+/// Picks the source of an `img`, or the best supported one of a `picture`.
+fn image_src(el: ElementRef, base: &Url) -> Option<String> {
+    let mut sources = vec![];
+    if el.value().name() == "img" {
+        sources.extend(el.value().attr("src"));
+    } else {
+        for child in el.children().filter_map(ElementRef::wrap) {
+            match child.value().name() {
+                "source" => sources.extend(
+                    child
+                        .value()
+                        .attr("srcset")
+                        .and_then(|srcset| srcset.split([',', ' ']).next()),
+                ),
+                "img" => sources.extend(child.value().attr("src")),
+                _ => {}
+            }
+        }
+    }
+
+    let sources = sources
+        .into_iter()
+        .filter_map(|src| base.join(src.trim()).ok())
+        .collect::<Vec<_>>();
+    IMAGE_FORMATS
+        .iter()
+        .find_map(|format| {
+            sources
+                .iter()
+                .find(|src| extension(src).is_some_and(|ext| ext == *format))
+        })
+        .or_else(|| sources.first())
+        .map(ToString::to_string)
+}
+
+/// Lowercase extension of the file a URL points to.
+pub fn extension(url: &Url) -> Option<String> {
+    let name = url.path_segments()?.next_back()?;
+    let (_, ext) = name.rsplit_once('.')?;
+    Some(ext.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn base() -> Url {
+        Url::parse("https://anto.pt/articles/a").unwrap()
+    }
+
+    #[test]
+    fn picks_the_best_image_sources() {
+        let html = r#"<picture><source srcset="/i.avif 1x"><source srcset="/i.png"><img src="/i.webp"></picture><img src="b.jpg">"#;
+        assert_eq!(
+            image_urls(html, &base()),
+            ["https://anto.pt/i.png", "https://anto.pt/articles/b.jpg"]
+        );
+    }
+
+    #[test]
+    fn converts_to_xhtml() {
+        let html = concat!(
+            r##"<h2 id="x">A &amp; B<a class="heading-anchor" href="#x">#</a></h2>"##,
+            r#"<p hx-get="/x" onclick="y()">1 &lt; 2<br><script>z()</script></p>"#,
+            r#"<iframe src="/v"></iframe>"#,
+        );
+        assert_eq!(
+            to_xhtml(html, &base(), &HashMap::new(), &HashMap::new()),
+            r#"<h2 id="x">A &amp; B</h2><p>1 &lt; 2<br/></p><a href="https://anto.pt/v">https://anto.pt/v</a>"#
+        );
+    }
+
+    #[test]
+    fn points_images_and_links_into_the_book() {
+        let images = HashMap::from([(
+            "https://anto.pt/i.png".to_string(),
+            "images/0.png".to_string(),
+        )]);
+        let chapters = HashMap::from([(
+            "https://anto.pt/articles/b".to_string(),
+            "b.xhtml".to_string(),
+        )]);
+        let html = r##"<img src="/i.png" alt="I"><img src="/j.png" alt="J"><a href="/articles/b#s">b</a><a href="/c">c</a><a href="#t">t</a>"##;
+        assert_eq!(
+            to_xhtml(html, &base(), &images, &chapters),
+            r##"<img src="images/0.png" alt="I"/>J<a href="b.xhtml#s">b</a><a href="https://anto.pt/c">c</a><a href="#t">t</a>"##
+        );
+    }
+}
@@ -0,0 +1,226 @@
+use std::io::Write;
+
+use flate2::{write::DeflateEncoder, Compression};
+
+const LOCAL_FILE_HEADER: u32 = 0x0403_4b50;
+const CENTRAL_DIRECTORY_HEADER: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x0605_4b50;
+const VERSION: u16 = 20;
+const STORED: u16 = 0;
+const DEFLATED: u16 = 8;
+/// 1980-01-01, the earliest date ZIP can represent. Entries don't carry
+/// meaningful dates, and fixing them keeps archives reproducible.
+const DOS_DATE: u16 = (1 << 5) | 1;
+
+struct Entry {
+    name: String,
+    method: u16,
+    crc: u32,
+    compressed_size: u32,
+    size: u32,
+    offset: u32,
+}
+
+/// Minimal writer of ZIP archives, as EPUB needs.
+#[derive(Default)]
+pub struct ZipWriter {
+    buf: Vec<u8>,
+    entries: Vec<Entry>,
+}
+
+impl ZipWriter {
+    /// Adds a file without compressing it, as required for the `mimetype`
+    /// of an EPUB.
+    pub fn store(&mut self, name: &str, data: &[u8]) {
+        self.add(name, data, STORED, data);
+    }
+
+    pub fn deflate(&mut self, name: &str, data: &[u8]) {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        match encoder.write_all(data).and_then(|()| encoder.finish()) {
+            Ok(compressed) => self.add(name, data, DEFLATED, &compressed),
+            Err(_) => self.store(name, data),
+        }
+    }
+
+    fn add(&mut self, name: &str, data: &[u8], method: u16, compressed: &[u8]) {
+        let entry = Entry {
+            name: name.to_string(),
+            method,
+            crc: crc32fast::hash(data),
+            compressed_size: to_u32(compressed.len()),
+            size: to_u32(data.len()),
+            offset: to_u32(self.buf.len()),
+        };
+
+        self.u32(LOCAL_FILE_HEADER);
+        self.u16(VERSION);
+        self.u16(0); // flags
+        self.u16(entry.method);
+        self.u16(0); // time
+        self.u16(DOS_DATE);
+        self.u32(entry.crc);
+        self.u32(entry.compressed_size);
+        self.u32(entry.size);
+        self.u16(to_u16(entry.name.len()));
+        self.u16(0); // extra field length
+        self.buf.extend_from_slice(entry.name.as_bytes());
+        self.buf.extend_from_slice(compressed);
+
+        self.entries.push(entry);
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        let start = self.buf.len();
+        let entries = std::mem::take(&mut self.entries);
+        for entry in &entries {
+            self.u32(CENTRAL_DIRECTORY_HEADER);
+            self.u16(VERSION); // version made by
+            self.u16(VERSION); // version needed to extract
+            self.u16(0); // flags
+            self.u16(entry.method);
+            self.u16(0); // time
+            self.u16(DOS_DATE);
+            self.u32(entry.crc);
+            self.u32(entry.compressed_size);
+            self.u32(entry.size);
+            self.u16(to_u16(entry.name.len()));
+            self.u16(0); // extra field length
+            self.u16(0); // comment length
+            self.u16(0); // disk number
+            self.u16(0); // internal attributes
+            self.u32(0); // external attributes
+            self.u32(entry.offset);
+            self.buf.extend_from_slice(entry.name.as_bytes());
+        }
+        let size = self.buf.len() - start;
+
+        self.u32(END_OF_CENTRAL_DIRECTORY);
+        self.u16(0); // disk number
+        self.u16(0); // disk with the central directory
+        self.u16(to_u16(entries.len()));
+        self.u16(to_u16(entries.len()));
+        self.u32(to_u32(size));
+        self.u32(to_u32(start));
+        self.u16(0); // comment length
+        self.buf
+    }
+
+    fn u16(&mut self, n: u16) {
+        self.buf.extend_from_slice(&n.to_le_bytes());
+    }
+
+    fn u32(&mut self, n: u32) {
+        self.buf.extend_from_slice(&n.to_le_bytes());
+    }
+}
+
+// without ZIP64, sizes and counts must fit the header fields
+fn to_u32(n: usize) -> u32 {
+    u32::try_from(n).expect("ZIP archive too large")
+}
+
+fn to_u16(n: usize) -> u16 {
+    u16::try_from(n).expect("too many entries in ZIP archive")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use flate2::read::DeflateDecoder;
+
+    use super::*;
+
+    fn u16_at(buf: &[u8], at: usize) -> u16 {
+        u16::from_le_bytes(buf[at..at + 2].try_into().unwrap())
+    }
+
+    fn u32_at(buf: &[u8], at: usize) -> u32 {
+        u32::from_le_bytes(buf[at..at + 4].try_into().unwrap())
+    }
+
+    /// Reads the entries of an archive back through its central directory,
+    /// checking that local headers agree with it.
+    fn read(zip: &[u8]) -> Vec<(String, u16, Vec<u8>)> {
+        let end = zip.len() - 22;
+        assert_eq!(u32_at(zip, end), END_OF_CENTRAL_DIRECTORY);
+        let count = usize::from(u16_at(zip, end + 10));
+        let mut at = u32_at(zip, end + 16) as usize;
+        assert_eq!(at + u32_at(zip, end + 12) as usize, end);
+
+        let mut entries = vec![];
+        for _ in 0..count {
+            assert_eq!(u32_at(zip, at), CENTRAL_DIRECTORY_HEADER);
+            let method = u16_at(zip, at + 10);
+            let crc = u32_at(zip, at + 16);
+            let compressed_size = u32_at(zip, at + 20) as usize;
+            let size = u32_at(zip, at + 24) as usize;
+            let name_len = usize::from(u16_at(zip, at + 28));
+            let offset = u32_at(zip, at + 42) as usize;
+            let name = String::from_utf8(zip[at + 46..at + 46 + name_len].to_vec()).unwrap();
+            at += 46 + name_len;
+
+            assert_eq!(u32_at(zip, offset), LOCAL_FILE_HEADER);
+            assert_eq!(u16_at(zip, offset + 8), method);
+            assert_eq!(u32_at(zip, offset + 14), crc);
+            assert_eq!(&zip[offset + 30..offset + 30 + name_len], name.as_bytes());
+            let start = offset + 30 + name_len + usize::from(u16_at(zip, offset + 28));
+            let raw = &zip[start..start + compressed_size];
+            let data = match method {
+                STORED => raw.to_vec(),
+                DEFLATED => {
+                    let mut data = vec![];
+                    DeflateDecoder::new(raw).read_to_end(&mut data).unwrap();
+                    data
+                }
+                _ => panic!("unknown method {method}"),
+            };
+            assert_eq!(data.len(), size);
+            assert_eq!(crc32fast::hash(&data), crc);
+            entries.push((name, method, data));
+        }
+        entries
+    }
+
+    #[test]
+    fn reads_back_stored_and_deflated_entries() {
+        let text = "lorem ipsum dolor sit amet ".repeat(100);
+        let mut zip = ZipWriter::default();
+        zip.store("mimetype", b"application/epub+zip");
+        zip.deflate("OEBPS/text.xhtml", text.as_bytes());
+        zip.deflate("empty", b"");
+        let zip = zip.finish();
+
+        let entries = read(&zip);
+        assert_eq!(entries.len(), 3);
+        assert_eq!(
+            entries[0],
+            (
+                "mimetype".to_string(),
+                STORED,
+                b"application/epub+zip".to_vec()
+            )
+        );
+        // EPUB readers find the mimetype right after its local header
+        assert_eq!(&zip[30..38], b"mimetype");
+        assert_eq!(&zip[38..58], b"application/epub+zip");
+        assert_eq!(
+            entries[1],
+            (
+                "OEBPS/text.xhtml".to_string(),
+                DEFLATED,
+                text.as_bytes().to_vec()
+            )
+        );
+        assert_eq!(entries[2], ("empty".to_string(), DEFLATED, vec![]));
+        assert!(zip.len() < text.len());
+    }
+
+    #[test]
+    fn writes_empty_archives() {
+        let zip = ZipWriter::default().finish();
+        assert_eq!(zip.len(), 22);
+        assert!(read(&zip).is_empty());
+    }
+}
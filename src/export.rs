@@ -0,0 +1,168 @@
+use std::{convert::Infallible, error::Error, fs, path::Path};
+
+use async_trait::async_trait;
+use axum::{
+    body::Body,
+    extract::FromRequestParts,
+    http::{request::Parts, Request},
+    Router,
+};
+use percent_encoding::percent_decode_str;
+use rscx::context::use_context;
+use tower::ServiceExt;
+
+use crate::{
+    apps::AppsRepo,
+    articles::ArticlesRepo,
+    bookmarks::BookmarksRepo,
+    feeds::FeedFormat,
+    tags::{count_tags, tag_path},
+};
+
+/// Whether a request is made by `univrs export`, which adds it to the
+/// extensions of its requests.
+///
+/// Pages provide it to their components as a context: parts of the pages
+/// that need the server, like the like button, are left out when exporting.
+#[derive(Clone, Debug, Default)]
+pub struct Export(pub bool);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Export
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        Ok(parts.extensions.get::<Self>().cloned().unwrap_or_default())
+    }
+}
+
+/// Whether the page being rendered is being exported, according to the
+/// [`Export`] provided by the page.
+#[must_use]
+pub fn is_exporting() -> bool {
+    use_context::<Export>().is_some_and(|export| export.0)
+}
+
+/// Renders every public route of `router` into `dir`, along with the
+/// `static` directory, so that the site can be served by any static host.
+///
+/// Pages are written as `<path>/index.html`, everything else (feeds, social
+/// images, EPUBs) as the file the path points to.
+///
+/// # Errors
+///
+/// Returns an error if a route doesn't answer with `200 OK` or if a file
+/// can't be written.
+pub async fn run(
+    router: Router,
+    dir: &Path,
+    articles_repo: &ArticlesRepo,
+    apps_repo: &AppsRepo,
+    bookmarks_repo: &BookmarksRepo,
+) -> Result<(), Box<dyn Error>> {
+    let mut pages = vec![
+        "/".to_string(),
+        "/articles".to_string(),
+        "/uses".to_string(),
+        "/bookmarks".to_string(),
+        "/tags".to_string(),
+    ];
+    let mut files = vec![
+        "/feed.xml".to_string(),
+        "/sitemap.xml".to_string(),
+        "/robots.txt".to_string(),
+    ];
+    files.extend(
+        FeedFormat::ALL
+            .iter()
+            .map(|format| format!("/articles/{}", format.file_name())),
+    );
+    files.push("/articles/archive.epub".to_string());
+
+    let articles = articles_repo.list();
+    let apps = apps_repo.list().await;
+    let bookmarks = bookmarks_repo.list().await;
+    for article in &articles {
+        pages.push(format!("/articles/{}", article.slug));
+        files.push(format!("/articles/{}/social-image.png", article.slug));
+        files.extend(["epub", "md", "txt"].map(|ext| format!("/articles/{}.{ext}", article.slug)));
+    }
+    let mut series = articles
+        .iter()
+        .filter_map(|a| a.series.as_ref())
+        .map(|s| format!("/series/{}", s.slug))
+        .collect::<Vec<_>>();
+    series.sort();
+    series.dedup();
+    pages.extend(series);
+    pages.extend(apps.iter().map(|app| format!("/uses/{}", app.slug)));
+    pages.extend(
+        bookmarks
+            .iter()
+            .map(|bookmark| format!("/bookmarks/{}", bookmark.slug)),
+    );
+    for tag in count_tags(&articles, &bookmarks, &apps).into_keys() {
+        pages.push(tag_path(&tag));
+        files.push(format!("{}/atom.xml", tag_path(&tag)));
+    }
+
+    for page in &pages {
+        let path = dir.join(file_path(page)).join("index.html");
+        export_route(&router, page, &path).await?;
+    }
+    for file in &files {
+        let path = dir.join(file_path(file));
+        export_route(&router, file, &path).await?;
+    }
+    copy_dir(Path::new("static"), &dir.join("static"))?;
+
+    tracing::info!(
+        "exported {} page(s) and {} file(s) to {}",
+        pages.len(),
+        files.len(),
+        dir.display()
+    );
+    Ok(())
+}
+
+/// Path of the file a route is written to, relative to the export
+/// directory. Static hosts decode the paths they are asked for, e.g. the page
+/// of the `c#` tag is written to `tags/c#`.
+fn file_path(route: &str) -> String {
+    percent_decode_str(route.trim_start_matches('/'))
+        .decode_utf8_lossy()
+        .into_owned()
+}
+
+async fn export_route(router: &Router, route: &str, path: &Path) -> Result<(), Box<dyn Error>> {
+    let mut req = Request::get(route).body(Body::empty())?;
+    req.extensions_mut().insert(Export(true));
+    let res = router.clone().oneshot(req).await?;
+    if !res.status().is_success() {
+        return Err(format!("{route}: {}", res.status()).into());
+    }
+
+    let body = hyper::body::to_bytes(res.into_body()).await?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, body)?;
+    Ok(())
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<(), Box<dyn Error>> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
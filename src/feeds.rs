@@ -0,0 +1,670 @@
+use std::{cmp::Reverse, fmt::Write};
+
+use atom_syndication::{
+    Category, CategoryBuilder, ContentBuilder, EntryBuilder, FeedBuilder, LinkBuilder,
+    PersonBuilder, TextBuilder,
+};
+use axum::{
+    http::{
+        header::{CONTENT_TYPE, VARY},
+        HeaderMap, StatusCode,
+    },
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, FixedOffset, Utc};
+use reqwest::Url;
+use rscx::html_escape::{decode_html_entities, encode_double_quoted_attribute, encode_text};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    apps::App,
+    articles::Article,
+    bookmarks::Bookmark,
+    conditional::{conditional, etag, not_modified_since},
+};
+
+/// Number of articles included in a feed.
+const FEED_ENTRIES: usize = 10;
+
+const AUTHOR: &str = "Antonio Pitasi";
+
+const ACTIVITY_FEED_URL: &str = "https://anto.pt/feed.xml";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FeedFormat {
+    Atom,
+    Rss,
+    Json,
+}
+
+impl FeedFormat {
+    pub const ALL: [FeedFormat; 3] = [FeedFormat::Atom, FeedFormat::Rss, FeedFormat::Json];
+
+    #[must_use]
+    pub fn content_type(self) -> &'static str {
+        match self {
+            FeedFormat::Atom => "application/atom+xml",
+            FeedFormat::Rss => "application/rss+xml",
+            FeedFormat::Json => "application/feed+json",
+        }
+    }
+
+    /// Name of the file serving the feed in this format, relative to the
+    /// page the feed is about.
+    #[must_use]
+    pub fn file_name(self) -> &'static str {
+        match self {
+            FeedFormat::Atom => "atom.xml",
+            FeedFormat::Rss => "rss.xml",
+            FeedFormat::Json => "feed.json",
+        }
+    }
+
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            FeedFormat::Atom => "Atom",
+            FeedFormat::Rss => "RSS",
+            FeedFormat::Json => "JSON Feed",
+        }
+    }
+
+    /// Picks the format preferred by an `Accept` header, defaulting to Atom.
+    pub fn from_accept(accept: &str) -> Self {
+        let mut best = (FeedFormat::Atom, 0.0);
+        for range in accept.split(',') {
+            let mut params = range.split(';').map(str::trim);
+            let format = match params.next().unwrap_or_default() {
+                "application/atom+xml"
+                | "application/xml"
+                | "text/xml"
+                | "application/*"
+                | "*/*" => FeedFormat::Atom,
+                "application/rss+xml" => FeedFormat::Rss,
+                "application/feed+json" | "application/json" => FeedFormat::Json,
+                _ => continue,
+            };
+            let quality = params
+                .find_map(|p| p.strip_prefix("q="))
+                .and_then(|q| q.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            if quality > best.1 {
+                best = (format, quality);
+            }
+        }
+        best.0
+    }
+}
+
+#[derive(Deserialize)]
+pub struct FeedQuery {
+    pub page: Option<usize>,
+}
+
+/// A feed of articles, renderable in any [`FeedFormat`].
+///
+/// Feeds are paged as described by RFC 5005: the first page has the latest
+/// articles and links to the next ones, so that the whole archive can be
+/// reached.
+pub struct ArticlesFeed<'a> {
+    pub title: &'a str,
+    /// URL of the page the feed is about. The feed itself is served at
+    /// `{url}/{format.file_name()}`.
+    pub url: &'a str,
+    pub articles: &'a [Article],
+    /// Page to render, starting from 1.
+    pub page: usize,
+    /// Whether the feed links to its other pages.
+    pub paged: bool,
+    /// When the articles were loaded: their content may have changed since
+    /// then, even if their dates didn't.
+    pub loaded_at: DateTime<FixedOffset>,
+}
+
+impl ArticlesFeed<'_> {
+    #[must_use]
+    pub fn self_url(&self, format: FeedFormat) -> String {
+        format!("{}/{}", self.url, format.file_name())
+    }
+
+    fn page_url(&self, format: FeedFormat, page: usize) -> String {
+        if page == 1 {
+            self.self_url(format)
+        } else {
+            format!("{}?page={page}", self.self_url(format))
+        }
+    }
+
+    fn page_count(&self) -> usize {
+        self.articles.len().div_ceil(FEED_ENTRIES).max(1)
+    }
+
+    /// `(rel, href)` of the links between the pages of the feed.
+    fn page_links(&self, format: FeedFormat) -> Vec<(&'static str, String)> {
+        if !self.paged {
+            return vec![("self", self.self_url(format))];
+        }
+        let mut links = vec![
+            ("self", self.page_url(format, self.page)),
+            ("first", self.page_url(format, 1)),
+            ("last", self.page_url(format, self.page_count())),
+        ];
+        if self.page > 1 {
+            links.push(("previous", self.page_url(format, self.page - 1)));
+        }
+        if self.page < self.page_count() {
+            links.push(("next", self.page_url(format, self.page + 1)));
+        }
+        links
+    }
+
+    fn entries(&self) -> impl Iterator<Item = (String, &Article)> {
+        self.articles
+            .iter()
+            .skip((self.page - 1) * FEED_ENTRIES)
+            .take(FEED_ENTRIES)
+            .map(|article| {
+                (
+                    format!("https://anto.pt/articles/{}", article.slug),
+                    article,
+                )
+            })
+    }
+
+    fn last_modified(&self) -> DateTime<FixedOffset> {
+        self.articles
+            .first()
+            .map_or_else(|| Utc::now().fixed_offset(), |a| a.datetime)
+    }
+
+    #[must_use]
+    pub fn render(&self, format: FeedFormat) -> String {
+        match format {
+            FeedFormat::Atom => self.atom(),
+            FeedFormat::Rss => self.rss(),
+            FeedFormat::Json => self.json(),
+        }
+    }
+
+    /// Renders the feed in `format`, with the matching content type.
+    /// Answers `304 Not Modified` if the client already has it.
+    #[must_use]
+    pub fn into_response(self, format: FeedFormat, headers: &HeaderMap) -> Response {
+        if self.page == 0 || self.page > self.page_count() {
+            return (StatusCode::NOT_FOUND, "404 not found").into_response();
+        }
+
+        let last_modified = self
+            .articles
+            .first()
+            .map(|a| self.loaded_at.max(a.datetime));
+        if let Some(res) = last_modified.and_then(|lm| not_modified_since(headers, lm)) {
+            return res;
+        }
+
+        let body = self.render(format);
+        conditional(
+            headers,
+            &etag(body.as_bytes()),
+            last_modified,
+            (
+                [(CONTENT_TYPE, format.content_type()), (VARY, "Accept")],
+                body,
+            ),
+        )
+    }
+
+    fn atom(&self) -> String {
+        let entries = self
+            .entries()
+            .map(|(url, article)| article_entry(&url, article).build())
+            .collect::<Vec<_>>();
+
+        FeedBuilder::default()
+            .title(self.title)
+            .id(self.url.to_string())
+            .links(
+                self.page_links(FeedFormat::Atom)
+                    .into_iter()
+                    .map(|(rel, href)| {
+                        LinkBuilder::default()
+                            .rel(rel.to_string())
+                            .href(href)
+                            .build()
+                    })
+                    .chain([LinkBuilder::default().href(self.url.to_string()).build()])
+                    .collect::<Vec<_>>(),
+            )
+            .authors(vec![PersonBuilder::default()
+                .name(AUTHOR.to_string())
+                .build()])
+            .updated(self.last_modified())
+            .entries(entries)
+            .build()
+            .to_string()
+    }
+
+    fn rss(&self) -> String {
+        let mut xml = String::from(r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+        let _ = write!(
+            xml,
+            r#"<rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom"><channel><title>{}</title><link>{}</link><description>{}</description><lastBuildDate>{}</lastBuildDate>"#,
+            encode_text(self.title),
+            encode_text(self.url),
+            encode_text(self.title),
+            self.last_modified().to_rfc2822(),
+        );
+        for (rel, href) in self.page_links(FeedFormat::Rss) {
+            let _ = write!(
+                xml,
+                r#"<atom:link href="{}" rel="{rel}" type="{}"/>"#,
+                encode_double_quoted_attribute(&href),
+                FeedFormat::Rss.content_type(),
+            );
+        }
+        for (url, article) in self.entries() {
+            let _ = write!(
+                xml,
+                r#"<item><title>{}</title><link>{}</link><guid isPermaLink="true">{}</guid><pubDate>{}</pubDate>"#,
+                encode_text(&article.title),
+                encode_text(&url),
+                encode_text(&url),
+                article.datetime.to_rfc2822(),
+            );
+            for tag in &article.tags {
+                let _ = write!(xml, "<category>{}</category>", encode_text(tag));
+            }
+            let _ = write!(
+                xml,
+                "<description>{}</description></item>",
+                encode_text(&absolute_urls(&article.content, &url))
+            );
+        }
+        xml.push_str("</channel></rss>");
+        xml
+    }
+
+    fn json(&self) -> String {
+        let feed = JsonFeed {
+            version: "https://jsonfeed.org/version/1.1",
+            title: self.title,
+            home_page_url: self.url,
+            feed_url: self.page_url(FeedFormat::Json, self.page),
+            next_url: (self.page < self.page_count())
+                .then(|| self.page_url(FeedFormat::Json, self.page + 1)),
+            authors: vec![JsonFeedAuthor { name: AUTHOR }],
+            items: self
+                .entries()
+                .map(|(url, article)| JsonFeedItem {
+                    id: url.clone(),
+                    content_html: absolute_urls(&article.content, &url),
+                    url,
+                    title: &article.title,
+                    summary: format!("{} min read", article.reading_time),
+                    date_published: article.datetime.to_rfc3339(),
+                    tags: &article.tags,
+                })
+                .collect(),
+        };
+        serde_json::to_string(&feed).expect("failed to serialize json feed")
+    }
+}
+
+fn article_entry(url: &str, article: &Article) -> EntryBuilder {
+    let mut entry = EntryBuilder::default();
+    entry
+        .title(TextBuilder::default().value(article.title.clone()).build())
+        .id(url.to_string())
+        .links(vec![LinkBuilder::default()
+            .rel("alternate".to_string())
+            .mime_type(Some("text/html".to_string()))
+            .href(url.to_string())
+            .build()])
+        .categories(
+            article
+                .tags
+                .iter()
+                .map(|tag| CategoryBuilder::default().term(tag.clone()).build())
+                .collect::<Vec<_>>(),
+        )
+        .summary(Some(
+            TextBuilder::default()
+                .value(format!("{} min read", article.reading_time))
+                .build(),
+        ))
+        .published(Some(article.datetime))
+        .updated(article.datetime)
+        .content(Some(
+            ContentBuilder::default()
+                .content_type(Some("html".to_string()))
+                .value(Some(absolute_urls(&article.content, url)))
+                .build(),
+        ));
+    entry
+}
+
+fn bookmark_entry(bookmark: &Bookmark) -> EntryBuilder {
+    let url = format!("https://anto.pt/bookmarks/{}", bookmark.slug);
+    let content = format!(
+        r#"<p>{}</p><p><a href="{}">{}</a></p>"#,
+        encode_text(&bookmark.description),
+        encode_double_quoted_attribute(&bookmark.url),
+        encode_text(&bookmark.url),
+    );
+    let created_at = bookmark.created_at.fixed_offset();
+
+    let mut entry = EntryBuilder::default();
+    entry
+        .title(TextBuilder::default().value(bookmark.title.clone()).build())
+        .id(url.clone())
+        .links(vec![
+            LinkBuilder::default()
+                .rel("alternate".to_string())
+                .mime_type(Some("text/html".to_string()))
+                .href(url)
+                .build(),
+            LinkBuilder::default()
+                .rel("related".to_string())
+                .href(bookmark.url.clone())
+                .build(),
+        ])
+        .categories(
+            bookmark
+                .tags
+                .iter()
+                .map(|tag| CategoryBuilder::default().term(tag.clone()).build())
+                .collect::<Vec<_>>(),
+        )
+        .summary(Some(
+            TextBuilder::default()
+                .value(bookmark.description.clone())
+                .build(),
+        ))
+        .published(Some(created_at))
+        .updated(created_at)
+        .content(Some(
+            ContentBuilder::default()
+                .content_type(Some("html".to_string()))
+                .value(Some(content))
+                .build(),
+        ));
+    entry
+}
+
+fn app_entry(app: &App) -> EntryBuilder {
+    let url = format!("https://anto.pt/uses/{}", app.slug);
+    let created_at = app.created_at.fixed_offset();
+
+    let mut entry = EntryBuilder::default();
+    entry
+        .title(TextBuilder::default().value(app.name.clone()).build())
+        .id(url.clone())
+        .links(vec![
+            LinkBuilder::default()
+                .rel("alternate".to_string())
+                .mime_type(Some("text/html".to_string()))
+                .href(url.clone())
+                .build(),
+            LinkBuilder::default()
+                .rel("related".to_string())
+                .href(app.url.clone())
+                .build(),
+        ])
+        .categories(
+            app.tags
+                .iter()
+                .map(|tag| CategoryBuilder::default().term(tag.clone()).build())
+                .collect::<Vec<_>>(),
+        )
+        .published(Some(created_at))
+        .updated(created_at)
+        .content(Some(
+            ContentBuilder::default()
+                .content_type(Some("html".to_string()))
+                .value(Some(absolute_urls(&app.description, &url)))
+                .build(),
+        ));
+    entry
+}
+
+/// Marks an entry of the activity feed with the type of content it is
+/// about, using a dedicated scheme so it can't be confused with a tag.
+fn type_category(term: &str, label: &str) -> Category {
+    CategoryBuilder::default()
+        .term(term.to_string())
+        .scheme(Some(format!("{ACTIVITY_FEED_URL}#type")))
+        .label(Some(label.to_string()))
+        .build()
+}
+
+/// Renders an Atom feed of the latest articles, bookmarks and apps, newest
+/// first.
+#[must_use]
+pub fn activity_feed(articles: &[Article], bookmarks: &[Bookmark], apps: &[App]) -> String {
+    mixed_feed(
+        "Antonio Pitasi",
+        "https://anto.pt/",
+        ACTIVITY_FEED_URL,
+        articles,
+        bookmarks,
+        apps,
+    )
+}
+
+/// Like [`activity_feed`], for the articles, bookmarks and apps with `tag`.
+/// `path` is the path of the page of the tag.
+#[must_use]
+pub fn tag_feed(
+    tag: &str,
+    path: &str,
+    articles: &[Article],
+    bookmarks: &[Bookmark],
+    apps: &[App],
+) -> String {
+    mixed_feed(
+        &format!("Antonio Pitasi about #{tag}"),
+        &format!("https://anto.pt{path}"),
+        &format!("https://anto.pt{path}/atom.xml"),
+        articles,
+        bookmarks,
+        apps,
+    )
+}
+
+fn mixed_feed(
+    title: &str,
+    url: &str,
+    self_url: &str,
+    articles: &[Article],
+    bookmarks: &[Bookmark],
+    apps: &[App],
+) -> String {
+    let mut entries = articles
+        .iter()
+        .map(|article| {
+            let url = format!("https://anto.pt/articles/{}", article.slug);
+            (
+                article_entry(&url, article),
+                type_category("article", "Article"),
+            )
+        })
+        .chain(
+            bookmarks
+                .iter()
+                .map(|b| (bookmark_entry(b), type_category("bookmark", "Bookmark"))),
+        )
+        .chain(
+            apps.iter()
+                .map(|a| (app_entry(a), type_category("app", "Uses"))),
+        )
+        .map(|(entry, category)| {
+            let mut entry = entry.build();
+            entry.categories.insert(0, category);
+            entry
+        })
+        .collect::<Vec<_>>();
+    entries.sort_by_key(|e| Reverse(e.updated));
+    entries.truncate(FEED_ENTRIES);
+
+    let updated = entries
+        .first()
+        .map_or_else(|| Utc::now().fixed_offset(), |e| e.updated);
+
+    FeedBuilder::default()
+        .title(title)
+        .subtitle(Some(
+            TextBuilder::default()
+                .value("Articles, bookmarks and apps from anto.pt".to_string())
+                .build(),
+        ))
+        .id(url.to_string())
+        .links(vec![
+            LinkBuilder::default()
+                .rel("self".to_string())
+                .href(self_url.to_string())
+                .build(),
+            LinkBuilder::default().href(url.to_string()).build(),
+        ])
+        .authors(vec![PersonBuilder::default()
+            .name(AUTHOR.to_string())
+            .build()])
+        .updated(updated)
+        .entries(entries)
+        .build()
+        .to_string()
+}
+
+/// Rewrites the relative `href`, `src` and `srcset` attributes of `html` to
+/// absolute URLs resolved against `base`, as feed readers show the content
+/// outside of the site.
+fn absolute_urls(html: &str, base: &str) -> String {
+    let Ok(base) = Url::parse(base) else {
+        return html.to_string();
+    };
+    let resolve = |url: &str| {
+        if Url::parse(url).is_ok() {
+            // already absolute
+            return url.to_string();
+        }
+        base.join(url)
+            .map_or_else(|_| url.to_string(), String::from)
+    };
+
+    let mut res = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some((start, is_srcset)) = find_url_attribute(rest) {
+        let end = rest[start..].find('"').map_or(rest.len(), |i| start + i);
+        let value = decode_html_entities(&rest[start..end]);
+        let value = if is_srcset {
+            value
+                .split(',')
+                .map(|candidate| {
+                    let candidate = candidate.trim();
+                    match candidate.split_once(' ') {
+                        Some((url, descriptor)) => format!("{} {descriptor}", resolve(url)),
+                        None => resolve(candidate),
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        } else {
+            resolve(&value)
+        };
+
+        res.push_str(&rest[..start]);
+        res.push_str(&encode_double_quoted_attribute(&value));
+        rest = &rest[end..];
+    }
+    res.push_str(rest);
+    res
+}
+
+/// Finds the first `href`, `src` or `srcset` attribute in `html`. Returns
+/// where its value starts and whether it is a `srcset`.
+fn find_url_attribute(html: &str) -> Option<(usize, bool)> {
+    let mut from = 0;
+    while let Some(i) = html[from..].find("=\"") {
+        let eq = from + i;
+        let name_start = html[..eq]
+            .rfind(|c: char| !c.is_ascii_alphanumeric() && c != '-')
+            .map_or(0, |i| i + 1);
+        from = eq + 2;
+        match &html[name_start..eq] {
+            "href" | "src" => return Some((from, false)),
+            "srcset" => return Some((from, true)),
+            // skip the value, which may itself contain `="`
+            _ => from = html[from..].find('"').map_or(html.len(), |i| from + i + 1),
+        }
+    }
+    None
+}
+
+/// See <https://www.jsonfeed.org/version/1.1/>.
+#[derive(Serialize)]
+struct JsonFeed<'a> {
+    version: &'static str,
+    title: &'a str,
+    home_page_url: &'a str,
+    feed_url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_url: Option<String>,
+    authors: Vec<JsonFeedAuthor>,
+    items: Vec<JsonFeedItem<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonFeedAuthor {
+    name: &'static str,
+}
+
+#[derive(Serialize)]
+struct JsonFeedItem<'a> {
+    id: String,
+    url: String,
+    title: &'a str,
+    content_html: String,
+    summary: String,
+    date_published: String,
+    tags: &'a [String],
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = "https://anto.pt/articles/a";
+
+    #[test]
+    fn resolves_relative_urls() {
+        assert_eq!(
+            absolute_urls(
+                r##"<a href="/tags/x">x</a><a href="#top">top</a><img src="b.png" />"##,
+                BASE
+            ),
+            r#"<a href="https://anto.pt/tags/x">x</a><a href="https://anto.pt/articles/a#top">top</a><img src="https://anto.pt/articles/b.png" />"#
+        );
+    }
+
+    #[test]
+    fn keeps_absolute_urls() {
+        let html = r#"<a href="https://x.y/?a=1&amp;b=2">x</a><a href="mailto:a@b.c">a</a>"#;
+        assert_eq!(absolute_urls(html, BASE), html);
+    }
+
+    #[test]
+    fn resolves_every_srcset_candidate() {
+        assert_eq!(
+            absolute_urls(
+                r#"<img srcset="/a.png 1x, https://x.y/b.png 2x, c.png" />"#,
+                BASE
+            ),
+            r#"<img srcset="https://anto.pt/a.png 1x, https://x.y/b.png 2x, https://anto.pt/articles/c.png" />"#
+        );
+    }
+
+    #[test]
+    fn skips_other_attributes() {
+        let html = r#"<img data-src="/a.png" alt="src" title="href=" />"#;
+        assert_eq!(absolute_urls(html, BASE), html);
+    }
+}
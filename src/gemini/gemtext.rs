@@ -0,0 +1,374 @@
+use std::fmt::Write;
+
+use comrak::{
+    nodes::{AstNode, NodeValue},
+    parse_document, Arena,
+};
+use scraper::{ElementRef, Html, Node};
+
+use crate::{
+    markdown::comrak_options,
+    mdx::{ComponentRegistry, Text},
+    plain_text::collapse_whitespace,
+    BASE_URL,
+};
+
+/// Converts the markdown of an article to gemtext.
+///
+/// Gemtext has no inline links: the links of a paragraph are listed right
+/// after it, as link lines. Components are flattened to text, as
+/// `components` describe them.
+pub fn from_markdown(markdown: &str, components: &ComponentRegistry) -> String {
+    let arena = Arena::new();
+    let root = parse_document(&arena, markdown, &comrak_options());
+
+    let mut blocks = vec![];
+    children(root, components, &mut blocks);
+    blocks.join("\n\n")
+}
+
+fn children<'a>(node: &'a AstNode<'a>, components: &ComponentRegistry, out: &mut Vec<String>) {
+    for child in node.children() {
+        block(child, components, out);
+    }
+}
+
+fn block<'a>(node: &'a AstNode<'a>, components: &ComponentRegistry, out: &mut Vec<String>) {
+    let value = node.data.borrow().value.clone();
+    match value {
+        NodeValue::Heading(heading) => {
+            let (text, links) = inline(node, components);
+            let level = usize::from(heading.level.min(3));
+            push_block(out, format!("{} {text}", "#".repeat(level)), links);
+        }
+        NodeValue::Paragraph => {
+            let (text, links) = inline(node, components);
+            push_block(out, text, links);
+        }
+        NodeValue::BlockQuote => {
+            let mut quoted = vec![];
+            children(node, components, &mut quoted);
+            let (links, text): (Vec<_>, Vec<_>) = quoted
+                .join("\n\n")
+                .lines()
+                .map(ToString::to_string)
+                .partition(|line| line.starts_with("=>"));
+            let text = text
+                .iter()
+                .map(|line| format!("> {line}").trim_end().to_string())
+                .collect::<Vec<_>>()
+                .join("\n");
+            push_block(out, text, links);
+        }
+        NodeValue::List(_) => {
+            let mut items = vec![];
+            let mut links = vec![];
+            list_items(node, components, &mut items, &mut links);
+            push_block(out, items.join("\n"), links);
+        }
+        NodeValue::CodeBlock(code) => {
+            let language = code.info.split_whitespace().next().unwrap_or_default();
+            let code = code.literal.trim_end_matches('\n');
+            out.push(format!("```{language}\n{code}\n```"));
+        }
+        NodeValue::HtmlBlock(html) => html_block(&html.literal, components, out),
+        NodeValue::Table(_) => {
+            let rows = node
+                .children()
+                .map(|row| {
+                    row.children()
+                        .map(|cell| inline(cell, components).0)
+                        .collect::<Vec<_>>()
+                        .join(" | ")
+                })
+                .collect::<Vec<_>>();
+            out.push(format!("```\n{}\n```", rows.join("\n")));
+        }
+        NodeValue::FootnoteDefinition(name) => {
+            let mut definition = vec![];
+            children(node, components, &mut definition);
+            if let Some(first) = definition.first_mut() {
+                *first = format!("[{name}] {first}");
+            }
+            out.extend(definition);
+        }
+        NodeValue::ThematicBreak => out.push("---".to_string()),
+        _ => children(node, components, out),
+    }
+}
+
+/// Pushes a block of text followed by its link lines.
+fn push_block(out: &mut Vec<String>, text: String, links: Vec<String>) {
+    let mut block = vec![];
+    if !text.trim().is_empty() {
+        block.push(text);
+    }
+    block.extend(links);
+    if !block.is_empty() {
+        out.push(block.join("\n"));
+    }
+}
+
+/// Gemtext lists can't be nested: nested items are listed along with their
+/// parents.
+fn list_items<'a>(
+    node: &'a AstNode<'a>,
+    components: &ComponentRegistry,
+    items: &mut Vec<String>,
+    links: &mut Vec<String>,
+) {
+    for item in node.children() {
+        let mut text = vec![];
+        let mut nested = vec![];
+        for child in item.children() {
+            if matches!(child.data.borrow().value, NodeValue::List(_)) {
+                nested.push(child);
+            } else {
+                let (paragraph, paragraph_links) = inline(child, components);
+                text.push(paragraph.replace('\n', " "));
+                links.extend(paragraph_links);
+            }
+        }
+        items.push(format!("* {}", text.join(" ").trim()));
+        for list in nested {
+            list_items(list, components, items, links);
+        }
+    }
+}
+
+/// Returns the text of an inline container and the link lines for the
+/// links and images in it.
+fn inline<'a>(node: &'a AstNode<'a>, components: &ComponentRegistry) -> (String, Vec<String>) {
+    let mut text = String::new();
+    let mut links = vec![];
+    inline_into(node, components, &mut text, &mut links);
+    (text.trim().to_string(), links)
+}
+
+fn inline_into<'a>(
+    node: &'a AstNode<'a>,
+    components: &ComponentRegistry,
+    text: &mut String,
+    links: &mut Vec<String>,
+) {
+    for child in node.children() {
+        let value = child.data.borrow().value.clone();
+        match value {
+            NodeValue::Text(t) => text.push_str(&t),
+            NodeValue::Code(code) => {
+                text.push('`');
+                text.push_str(&code.literal);
+                text.push('`');
+            }
+            NodeValue::SoftBreak => text.push(' '),
+            NodeValue::LineBreak => text.push('\n'),
+            NodeValue::Link(link) => {
+                let mut label = String::new();
+                inline_into(child, components, &mut label, links);
+                text.push_str(&label);
+                links.extend(link_line(&link.url, &label));
+            }
+            NodeValue::Image(link) => {
+                let (alt, _) = inline(child, components);
+                links.extend(link_line(&link.url, &alt));
+            }
+            NodeValue::FootnoteReference(name) => {
+                let _ = write!(text, "[{name}]");
+            }
+            NodeValue::HtmlInline(html) => inline_html(&html, components, text, links),
+            _ => inline_into(child, components, text, links),
+        }
+    }
+}
+
+/// Flattens the components and raw HTML of an HTML block.
+fn html_block(html: &str, components: &ComponentRegistry, out: &mut Vec<String>) {
+    let fragment = Html::parse_fragment(html);
+    html_children(fragment.root_element(), components, out);
+}
+
+fn html_children(el: ElementRef, components: &ComponentRegistry, out: &mut Vec<String>) {
+    for node in el.children() {
+        if let Some(el) = ElementRef::wrap(node) {
+            html_element(el, components, out);
+        } else if let Node::Text(text) = node.value() {
+            let text = collapse_whitespace(text);
+            if !text.trim().is_empty() {
+                out.push(text.trim().to_string());
+            }
+        }
+    }
+}
+
+/// Components that don't start a block are inline HTML, their children
+/// being the text that follows them in the paragraph.
+fn inline_html(
+    html: &str,
+    components: &ComponentRegistry,
+    text: &mut String,
+    links: &mut Vec<String>,
+) {
+    let fragment = Html::parse_fragment(html);
+    for el in fragment
+        .root_element()
+        .children()
+        .filter_map(ElementRef::wrap)
+    {
+        if let Some(Text::Labelled(label)) = component_text(el, components) {
+            let _ = write!(text, "{label}: ");
+        } else {
+            let mut blocks = vec![];
+            html_element(el, components, &mut blocks);
+            links.extend(blocks.into_iter().filter(|b| b.starts_with("=>")));
+        }
+    }
+}
+
+fn component_text(el: ElementRef, components: &ComponentRegistry) -> Option<Text> {
+    let attributes = el
+        .value()
+        .attrs()
+        .map(|(name, value)| (name.to_string(), Some(value.to_string())))
+        .collect();
+    components.text(el.value().name(), attributes)
+}
+
+fn html_element(el: ElementRef, components: &ComponentRegistry, out: &mut Vec<String>) {
+    // children separated from the tags by blank lines are blocks of their
+    // own, and only the title is taken from the tag
+    match component_text(el, components) {
+        Some(Text::Children) => html_children(el, components, out),
+        Some(Text::Labelled(label)) => out.push(format!(
+            "{label}: {}",
+            collapse_whitespace(&el.text().collect::<String>()).trim()
+        )),
+        Some(Text::Titled(title)) => {
+            if !title.is_empty() {
+                out.push(title);
+            }
+            html_children(el, components, out);
+        }
+        Some(Text::Link { url, label }) => out.extend(link_line(&url, &label)),
+        Some(Text::Image { src, alt }) => out.extend(link_line(&src, &alt)),
+        None => raw_html_element(el, out),
+    }
+}
+
+/// Flattens an element that isn't a component.
+fn raw_html_element(el: ElementRef, out: &mut Vec<String>) {
+    match el.value().name() {
+        "img" | "picture" | "iframe" | "video" | "audio" => {
+            let label = std::iter::once(el)
+                .chain(el.descendants().filter_map(ElementRef::wrap))
+                .find_map(|e| e.value().attr("alt").or_else(|| e.value().attr("title")))
+                .unwrap_or_default();
+            out.extend(
+                std::iter::once(el)
+                    .chain(el.descendants().filter_map(ElementRef::wrap))
+                    .find_map(|e| e.value().attr("src"))
+                    .and_then(|src| link_line(src, label)),
+            );
+        }
+        "script" | "style" => {}
+        _ => {
+            let text = collapse_whitespace(&el.text().collect::<String>());
+            if !text.trim().is_empty() {
+                out.push(text.trim().to_string());
+            }
+        }
+    }
+}
+
+/// Link line for `url`. Articles are linked on the capsule, at the same
+/// path as on the web, the rest of the site on the web. Links to sections
+/// of the same page have no equivalent and are dropped.
+fn link_line(url: &str, label: &str) -> Option<String> {
+    if url.is_empty() || url.starts_with('#') {
+        return None;
+    }
+    let url = url
+        .strip_prefix(BASE_URL)
+        .filter(|path| path.starts_with("/articles/"))
+        .unwrap_or(url);
+    let url = if url.starts_with("/articles/") {
+        url.to_string()
+    } else if url.starts_with('/') {
+        format!("{BASE_URL}{url}")
+    } else {
+        url.to_string()
+    };
+
+    let label = label.replace('\n', " ");
+    let label = label.trim();
+    if label.is_empty() || label == url {
+        Some(format!("=> {url}"))
+    } else {
+        Some(format!("=> {url} {label}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mdx::Component;
+
+    fn components() -> ComponentRegistry {
+        let mut registry = ComponentRegistry::default();
+        registry.register(
+            Component::new("Dialog", |_| async { Ok(String::new()) })
+                .text(|props| Text::Labelled(props.get("character").unwrap_or_default())),
+        );
+        registry.register(
+            Component::new("Tab", |_| async { Ok(String::new()) })
+                .text(|props| Text::Titled(props.get("label").unwrap_or_default())),
+        );
+        registry.register(
+            Component::new("AppCard", |_| async { Ok(String::new()) }).text(|props| Text::Link {
+                url: format!("/uses/{}", props.get::<String>("slug").unwrap_or_default()),
+                label: String::new(),
+            }),
+        );
+        registry
+    }
+
+    #[test]
+    fn lists_links_after_their_paragraph() {
+        assert_eq!(
+            from_markdown(
+                "# Title\n\nSee [this](/articles/a) and [that](/b), [here](#x).",
+                &components()
+            ),
+            format!(
+                "# Title\n\nSee this and that, here.\n=> /articles/a this\n=> {BASE_URL}/b that"
+            )
+        );
+    }
+
+    #[test]
+    fn flattens_lists_and_quotes() {
+        assert_eq!(
+            from_markdown("- a\n  - b\n\n> c\n> [d](https://x.y)", &components()),
+            "* a\n* b\n\n> c d\n=> https://x.y d"
+        );
+    }
+
+    #[test]
+    fn keeps_code_blocks() {
+        assert_eq!(
+            from_markdown("```rust\nfn main() {}\n```", &components()),
+            "```rust\nfn main() {}\n```"
+        );
+    }
+
+    #[test]
+    fn renders_components_as_text() {
+        let markdown = "<Dialog character=bulb pos=left>\nHi there\n</Dialog>\n\n\
+            <Tab label=\"First\">\n\nInside\n\n</Tab>\n\n\
+            <AppCard slug=\"x\" />\n\n\
+            <Unknown>\nText\n</Unknown>";
+        assert_eq!(
+            from_markdown(markdown, &components()),
+            format!("bulb: Hi there\n\nFirst\n\nInside\n\n=> {BASE_URL}/uses/x\n\nText")
+        );
+    }
+}
@@ -0,0 +1,174 @@
+mod gemtext;
+
+use std::{
+    error::Error, fs::File, io::BufReader, net::SocketAddr, path::Path, sync::Arc, time::Duration,
+};
+
+use reqwest::Url;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    time::timeout,
+};
+use tokio_rustls::{
+    rustls::{Certificate, PrivateKey, ServerConfig},
+    TlsAcceptor,
+};
+
+use crate::{
+    articles::{Article, ArticlesRepo},
+    mdx::ComponentRegistry,
+    BASE_URL,
+};
+
+/// A request is a URL of at most 1024 bytes, followed by CRLF.
+const MAX_REQUEST_LEN: u64 = 1026;
+
+/// Time given to clients to complete the TLS handshake and send their
+/// request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Serves the articles over the Gemini protocol, at the same paths as on
+/// the web: an index at `/` and `/articles`, and each article, as gemtext,
+/// at `/articles/:slug`.
+pub struct Server {
+    listener: TcpListener,
+    acceptor: TlsAcceptor,
+}
+
+impl Server {
+    /// Binds the server to `addr`. `cert` and `key` are PEM files; Gemini
+    /// clients trust certificates on first use, so a self-signed one will do.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the certificate or the key can't be loaded, or
+    /// if `addr` can't be bound.
+    pub async fn bind(addr: SocketAddr, cert: &Path, key: &Path) -> Result<Self, Box<dyn Error>> {
+        let config = tls_config(cert, key)?;
+        let listener = TcpListener::bind(addr).await?;
+        tracing::info!("gemini listening on {addr}");
+        Ok(Self {
+            listener,
+            acceptor: TlsAcceptor::from(Arc::new(config)),
+        })
+    }
+
+    pub async fn serve(self, articles_repo: ArticlesRepo) {
+        loop {
+            let (stream, peer) = match self.listener.accept().await {
+                Ok(conn) => conn,
+                Err(err) => {
+                    tracing::error!("failed to accept gemini connection: {err}");
+                    continue;
+                }
+            };
+            let acceptor = self.acceptor.clone();
+            let articles_repo = articles_repo.clone();
+            tokio::spawn(async move {
+                if let Err(err) = handle(&acceptor, stream, &articles_repo).await {
+                    tracing::warn!("gemini request from {peer} failed: {err}");
+                }
+            });
+        }
+    }
+}
+
+fn tls_config(cert: &Path, key: &Path) -> Result<ServerConfig, Box<dyn Error>> {
+    let certs = rustls_pemfile::certs(&mut BufReader::new(File::open(cert)?))?
+        .into_iter()
+        .map(Certificate)
+        .collect();
+    let key = rustls_pemfile::read_all(&mut BufReader::new(File::open(key)?))?
+        .into_iter()
+        .find_map(|item| match item {
+            rustls_pemfile::Item::RSAKey(key)
+            | rustls_pemfile::Item::PKCS8Key(key)
+            | rustls_pemfile::Item::ECKey(key) => Some(PrivateKey(key)),
+            _ => None,
+        })
+        .ok_or_else(|| format!("no private key in {}", key.display()))?;
+
+    let config = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)?;
+    Ok(config)
+}
+
+async fn handle(
+    acceptor: &TlsAcceptor,
+    stream: TcpStream,
+    articles_repo: &ArticlesRepo,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut stream = timeout(REQUEST_TIMEOUT, acceptor.accept(stream)).await??;
+
+    let mut request = String::new();
+    let mut reader = tokio::io::BufReader::new(&mut stream).take(MAX_REQUEST_LEN);
+    timeout(REQUEST_TIMEOUT, reader.read_line(&mut request)).await??;
+
+    let response = respond(&request, articles_repo);
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+fn respond(request: &str, articles_repo: &ArticlesRepo) -> String {
+    // a request that doesn't end with a newline was too long
+    let Some(request) = request.strip_suffix('\n') else {
+        return header(59, "Bad request");
+    };
+    let Ok(url) = Url::parse(request.trim_end_matches('\r')) else {
+        return header(59, "Bad request");
+    };
+    if url.scheme() != "gemini" {
+        return header(53, "Proxy request refused");
+    }
+
+    match url.path().trim_end_matches('/') {
+        "" | "/articles" => success(&index(articles_repo)),
+        path => match path
+            .strip_prefix("/articles/")
+            .and_then(|slug| articles_repo.get_article_by_slug(slug))
+        {
+            Some(article) => success(&article_page(&article, articles_repo.components())),
+            None => header(51, "Not found"),
+        },
+    }
+}
+
+fn header(status: u8, meta: &str) -> String {
+    format!("{status} {meta}\r\n")
+}
+
+fn success(body: &str) -> String {
+    header(20, "text/gemini; charset=utf-8; lang=en") + body
+}
+
+/// Lists the articles as a Gemini feed: link lines starting with their
+/// date, newest first.
+fn index(articles_repo: &ArticlesRepo) -> String {
+    let mut lines = vec!["# Antonio Pitasi's Articles".to_string(), String::new()];
+    lines.extend(articles_repo.list().iter().map(|article| {
+        format!(
+            "=> /articles/{} {} - {}",
+            article.slug,
+            article.datetime.format("%Y-%m-%d"),
+            article.title
+        )
+    }));
+    lines.push(String::new());
+    lines.push(format!("=> {BASE_URL} The website"));
+    lines.join("\n") + "\n"
+}
+
+fn article_page(article: &Article, components: &ComponentRegistry) -> String {
+    format!(
+        "# {}\n\n{} · {} min read\n\n{}\n\n=> /articles All articles\n=> {BASE_URL}/articles/{} Read it on the web\n",
+        article.title,
+        article.datetime.format("%B %d, %Y"),
+        article.reading_time,
+        gemtext::from_markdown(article.body(), components),
+        article.slug,
+    )
+}
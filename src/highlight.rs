@@ -0,0 +1,325 @@
+use std::{fmt::Write, ops::RangeInclusive};
+
+use once_cell::sync::Lazy;
+use rscx::html_escape::{encode_double_quoted_attribute, encode_text};
+use syntect::{
+    html::{ClassStyle, ClassedHTMLGenerator},
+    parsing::SyntaxSet,
+    util::LinesWithEndings,
+};
+
+/// Every token gets `hl-` prefixed classes named after its scopes (e.g.
+/// `hl-keyword hl-control`), which are themed in `static/style.css`.
+const CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed { prefix: "hl-" };
+
+static SYNTAXES: Lazy<SyntaxSet> = Lazy::new(SyntaxSet::load_defaults_newlines);
+
+/// Replaces the fenced code blocks of a markdown source with highlighted
+/// HTML, so that no JavaScript is needed to highlight them client-side.
+///
+/// The info string of a block can contain, besides the language:
+/// - `{3-5,8}` (or Hugo's `{hl_lines=["3-5", "8"]}`) to highlight lines;
+/// - `title="main.rs"` (or `filename=`) to add a caption to the block.
+#[must_use]
+pub fn highlight_code_blocks(markdown: &str) -> String {
+    let mut res = String::with_capacity(markdown.len());
+    let mut lines = markdown.split_inclusive('\n');
+
+    while let Some(line) = lines.next() {
+        let Some(fence) = Fence::parse(line) else {
+            res.push_str(line);
+            continue;
+        };
+
+        let mut code = String::new();
+        let mut closed = false;
+        for line in lines.by_ref() {
+            if fence.is_closed_by(line) {
+                closed = true;
+                break;
+            }
+            code.push_str(strip_indent(line, fence.indent));
+        }
+
+        let html = fence.info.render(&code);
+        // the block is indented like its fence, so that it stays within
+        // its list item, if any
+        for html_line in html.lines() {
+            res.push_str(&" ".repeat(fence.indent));
+            res.push_str(html_line);
+            res.push('\n');
+        }
+        if closed {
+            // a blank line ends the raw HTML block
+            res.push('\n');
+        }
+    }
+
+    res
+}
+
+/// Lines of `markdown`, split on `\n`, each with whether it is part of a
+/// fenced code block, fences included. As in [`highlight_code_blocks`], a
+/// block is only closed by a fence of the same marker, at least as long.
+pub fn fenced_lines(markdown: &str) -> impl Iterator<Item = (&str, bool)> {
+    let mut fence: Option<Fence> = None;
+    markdown.split('\n').map(move |line| {
+        if let Some(open) = &fence {
+            if open.is_closed_by(line) {
+                fence = None;
+            }
+            (line, true)
+        } else {
+            fence = Fence::parse(line);
+            (line, fence.is_some())
+        }
+    })
+}
+
+struct Fence {
+    indent: usize,
+    marker: char,
+    len: usize,
+    info: Info,
+}
+
+impl Fence {
+    fn parse(line: &str) -> Option<Self> {
+        let trimmed = line.trim_start_matches(' ');
+        let indent = line.len() - trimmed.len();
+        let marker = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+        let len = trimmed.len() - trimmed.trim_start_matches(marker).len();
+        if len < 3 {
+            return None;
+        }
+
+        let info = trimmed[len..].trim();
+        if marker == '`' && info.contains('`') {
+            return None;
+        }
+        Some(Self {
+            indent,
+            marker,
+            len,
+            info: Info::parse(info),
+        })
+    }
+
+    fn is_closed_by(&self, line: &str) -> bool {
+        let trimmed = line.trim_start_matches(' ');
+        let rest = trimmed.trim_start_matches(self.marker);
+        line.len() - trimmed.len() <= self.indent + 3
+            && trimmed.len() - rest.len() >= self.len
+            && rest.trim().is_empty()
+    }
+}
+
+/// Removes up to `indent` leading spaces from `line`.
+fn strip_indent(line: &str, indent: usize) -> &str {
+    let spaces = line.len() - line.trim_start_matches(' ').len();
+    &line[spaces.min(indent)..]
+}
+
+#[derive(Default)]
+struct Info {
+    lang: Option<String>,
+    title: Option<String>,
+    /// Ranges of lines to highlight, starting from 1. Kept as ranges, as
+    /// they may go way past the end of the block.
+    highlighted: Vec<RangeInclusive<usize>>,
+}
+
+impl Info {
+    fn parse(info: &str) -> Self {
+        let mut res = Self::default();
+        let mut rest = info.trim();
+
+        while !rest.is_empty() {
+            if let Some(braced) = rest.strip_prefix('{') {
+                let end = braced.find('}').unwrap_or(braced.len());
+                res.highlighted.extend(parse_line_ranges(&braced[..end]));
+                rest = braced.get(end + 1..).unwrap_or_default().trim_start();
+                continue;
+            }
+
+            let key_len = rest
+                .find(|c: char| c.is_whitespace() || c == '=' || c == '{')
+                .unwrap_or(rest.len());
+            let key = &rest[..key_len];
+            rest = &rest[key_len..];
+
+            if let Some(value) = rest.strip_prefix('=') {
+                let (value, remaining) = if let Some(quoted) = value.strip_prefix('"') {
+                    let end = quoted.find('"').unwrap_or(quoted.len());
+                    (&quoted[..end], quoted.get(end + 1..).unwrap_or_default())
+                } else {
+                    let end = value.find(char::is_whitespace).unwrap_or(value.len());
+                    (&value[..end], &value[end..])
+                };
+                if key == "title" || key == "filename" {
+                    res.title = Some(value.to_string());
+                }
+                rest = remaining;
+            } else if res.lang.is_none() && !key.is_empty() {
+                res.lang = Some(key.to_string());
+            }
+            rest = rest.trim_start();
+        }
+
+        res
+    }
+
+    fn render(&self, code: &str) -> String {
+        let syntaxes = &*SYNTAXES;
+        let syntax = self
+            .lang
+            .as_deref()
+            .and_then(|lang| syntaxes.find_syntax_by_token(lang))
+            .unwrap_or_else(|| syntaxes.find_syntax_plain_text());
+
+        let mut generator =
+            ClassedHTMLGenerator::new_with_class_style(syntax, syntaxes, CLASS_STYLE);
+        for line in LinesWithEndings::from(code) {
+            if let Err(err) = generator.parse_html_for_line_which_includes_newline(line) {
+                tracing::error!("failed to highlight code block: {err}");
+                return self.render_lines(encode_text(code).lines());
+            }
+        }
+        let html = generator.finalize();
+        self.render_lines(split_lines(&html).iter().map(String::as_str))
+    }
+
+    fn render_lines<'a>(&self, lines: impl Iterator<Item = &'a str>) -> String {
+        let mut res = String::from(r#"<figure class="code-block">"#);
+        if let Some(title) = &self.title {
+            let _ = write!(res, "<figcaption>{}</figcaption>", encode_text(title));
+        }
+        let lang = self.lang.as_deref().unwrap_or("text");
+        let _ = write!(
+            res,
+            r#"<pre><code class="language-{}">"#,
+            encode_double_quoted_attribute(lang)
+        );
+        for (i, line) in lines.enumerate() {
+            let class = if self.highlighted.iter().any(|r| r.contains(&(i + 1))) {
+                "line highlighted"
+            } else {
+                "line"
+            };
+            // newlines are part of the lines, so that copying the code
+            // keeps them
+            let _ = write!(res, "<span class=\"{class}\">{line}\n</span>");
+        }
+        res.push_str("</code></pre></figure>");
+        res
+    }
+}
+
+/// Parses line ranges such as `3-5,8` or Hugo's `hl_lines=["3-5", "8"]`.
+fn parse_line_ranges(s: &str) -> impl Iterator<Item = RangeInclusive<usize>> + '_ {
+    s.trim()
+        .trim_start_matches("hl_lines")
+        .trim_start_matches('=')
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .map(|range| range.trim_matches(|c: char| c == '"' || c == '[' || c == ']'))
+        .filter_map(|range| match range.split_once('-') {
+            Some((from, to)) => Some((from.parse().ok()?, to.parse().ok()?)),
+            None => range.parse().ok().map(|n| (n, n)),
+        })
+        .map(|(from, to)| from..=to)
+}
+
+/// Splits the output of the highlighter into lines, closing the tokens still
+/// open at the end of a line and reopening them on the next one, so that each
+/// line can be wrapped in its own element.
+fn split_lines(html: &str) -> Vec<String> {
+    let mut lines = vec![];
+    let mut open: Vec<&str> = vec![];
+
+    let mut segments = html.split('\n').collect::<Vec<_>>();
+    // the code ends with a newline, after which only closing tags are left
+    if segments
+        .last()
+        .is_some_and(|s| s.trim_start_matches("</span>").is_empty())
+    {
+        segments.pop();
+    }
+
+    for line in segments {
+        let mut res = open.concat();
+        let mut rest = line;
+        while let Some(start) = rest.find('<') {
+            let end = rest[start..]
+                .find('>')
+                .map_or(rest.len(), |i| start + i + 1);
+            let tag = &rest[start..end];
+            if tag.starts_with("</") {
+                open.pop();
+            } else {
+                open.push(tag);
+            }
+            res.push_str(&rest[..end]);
+            rest = &rest[end..];
+        }
+        res.push_str(rest);
+        res.push_str(&"</span>".repeat(open.len()));
+        lines.push(res);
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_line_ranges() {
+        let ranges = |s| parse_line_ranges(s).collect::<Vec<_>>();
+        assert_eq!(ranges("3-5,8"), [3..=5, 8..=8]);
+        assert_eq!(ranges(r#"hl_lines=["3-5", "8"]"#), [3..=5, 8..=8]);
+        assert_eq!(ranges("1-30000000"), [1..=30_000_000]);
+        assert_eq!(ranges("a, 2-b, 4"), [4..=4]);
+    }
+
+    #[test]
+    fn parses_info_strings() {
+        let info = Info::parse(r#"rust {2} title="src/main.rs" {4-5}"#);
+        assert_eq!(info.lang.as_deref(), Some("rust"));
+        assert_eq!(info.title.as_deref(), Some("src/main.rs"));
+        assert_eq!(info.highlighted, [2..=2, 4..=5]);
+    }
+
+    #[test]
+    fn closes_fences_with_the_same_marker() {
+        let fence = Fence::parse("  ````md\n").unwrap();
+        assert_eq!((fence.indent, fence.marker, fence.len), (2, '`', 4));
+        assert!(!fence.is_closed_by("```\n"));
+        assert!(!fence.is_closed_by("~~~~\n"));
+        assert!(fence.is_closed_by("`````  \n"));
+        assert!(Fence::parse("``not a fence\n").is_none());
+        assert!(Fence::parse("``` a`b\n").is_none());
+    }
+
+    #[test]
+    fn finds_fenced_lines() {
+        let markdown = "a\n````md\n```rust\nb\n```\n````\nc\n~~~\nd";
+        let code = fenced_lines(markdown)
+            .filter(|(_, in_code)| *in_code)
+            .map(|(line, _)| line)
+            .collect::<Vec<_>>();
+        assert_eq!(code, ["````md", "```rust", "b", "```", "````", "~~~", "d"]);
+    }
+
+    #[test]
+    fn highlights_lines() {
+        let html = highlight_code_blocks("```text {2}\na\nb\n```\nafter\n");
+        assert_eq!(
+            html,
+            "<figure class=\"code-block\"><pre><code class=\"language-text\">\
+             <span class=\"line\"><span class=\"hl-text hl-plain\">a</span>\n</span>\
+             <span class=\"line highlighted\"><span class=\"hl-text hl-plain\">b</span>\n</span>\
+             </code></pre></figure>\n\nafter\n"
+        );
+    }
+}
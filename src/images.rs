@@ -3,6 +3,46 @@ use std::fmt::Write;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use crate::mdx::{Component, ComponentRegistry, Text};
+
+const REMOTE_IMAGE_SOURCES: &[&str] = &["avif", "webp", "png", "jpeg", "svg"];
+
+pub fn register(registry: &mut ComponentRegistry) {
+    registry.register(
+        Component::new("RemoteImage", |props| async move {
+            let srcset = Srcset {
+                avif: props.get_optional("avif")?,
+                webp: props.get_optional("webp")?,
+                png: props.get_optional("png")?,
+                jpeg: props.get_optional("jpeg")?,
+                svg: props.get_optional("svg")?,
+            };
+            let alt = props.get_optional("alt")?.unwrap_or_default();
+            let class = props.get_optional("class")?.unwrap_or_default();
+            Ok(html! {
+                <RemoteImg srcset=srcset alt=alt class=class />
+            })
+        })
+        .optional::<String>("avif")
+        .optional::<String>("webp")
+        .optional::<String>("png")
+        .optional::<String>("jpeg")
+        .optional::<String>("svg")
+        .optional::<String>("alt")
+        .optional::<String>("class")
+        .one_of(REMOTE_IMAGE_SOURCES)
+        // Gemini clients open images with whatever viewer they have, so the
+        // most common formats come first
+        .text(|props| Text::Image {
+            src: ["png", "jpeg", "webp", "svg", "avif"]
+                .iter()
+                .find_map(|format| props.get(format).ok())
+                .unwrap_or_default(),
+            alt: props.get("alt").unwrap_or_default(),
+        }),
+    );
+}
+
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
 pub enum ImageSrc {
     Svg(String),
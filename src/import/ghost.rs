@@ -0,0 +1,87 @@
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
+use super::ImportedPost;
+
+/// Placeholder Ghost uses in exports instead of the site URL.
+const GHOST_URL: &str = "__GHOST_URL__";
+
+/// Reads the posts of a Ghost JSON export. Pages and posts that aren't
+/// published or scheduled are skipped.
+///
+/// Without `site_url`, posts still linking to `__GHOST_URL__` are an error.
+pub fn parse(input: &str, site_url: Option<&str>) -> Result<Vec<ImportedPost>, String> {
+    let export: Value =
+        serde_json::from_str(input).map_err(|err| format!("invalid Ghost export: {err}"))?;
+    // exports are wrapped in a `db` array, but the bare `data` object is
+    // accepted too
+    let data = export["db"]
+        .get(0)
+        .map_or(&export["data"], |db| &db["data"]);
+    let posts = data["posts"]
+        .as_array()
+        .ok_or("invalid Ghost export: missing `posts`")?;
+
+    let mut res = vec![];
+    for post in posts {
+        if post["type"].as_str().is_some_and(|t| t != "post") {
+            continue;
+        }
+        let title = post["title"].as_str().unwrap_or_default().to_string();
+        let status = post["status"].as_str().unwrap_or_default();
+        if !matches!(status, "published" | "scheduled") {
+            tracing::warn!("skipping `{title}`: it is not published (status `{status}`)");
+            continue;
+        }
+        let Some(html) = post["html"].as_str() else {
+            tracing::warn!("skipping `{title}`: the export has no HTML for it");
+            continue;
+        };
+        let datetime = ["published_at", "created_at"]
+            .iter()
+            .filter_map(|key| post[key].as_str())
+            .find_map(|date| DateTime::parse_from_rfc3339(date).ok())
+            .map_or_else(Utc::now, |date| date.with_timezone(&Utc));
+        let html = match site_url {
+            Some(url) => html.replace(GHOST_URL, url.trim_end_matches('/')),
+            None if html.contains(GHOST_URL) => {
+                return Err(format!(
+                    "`{title}` links to `{GHOST_URL}`: pass `--site-url` to replace it"
+                ))
+            }
+            None => html.to_string(),
+        };
+
+        res.push(ImportedPost {
+            slug: post["slug"].as_str().unwrap_or(&title).to_string(),
+            title,
+            datetime,
+            html,
+        });
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORT: &str = r#"{"db": [{"data": {"posts": [
+        {"title": "A", "slug": "a", "status": "published", "html": "<a href=\"__GHOST_URL__/b/\">b</a>"},
+        {"title": "B", "slug": "b", "status": "draft", "html": "<p>b</p>"},
+        {"title": "C", "slug": "c", "status": "published", "type": "page", "html": "<p>c</p>"}
+    ]}}]}"#;
+
+    #[test]
+    fn skips_pages_and_drafts() {
+        let posts = parse(EXPORT, Some("https://example.com/")).unwrap();
+        assert_eq!(posts.len(), 1);
+        assert_eq!(posts[0].slug, "a");
+        assert_eq!(posts[0].html, r#"<a href="https://example.com/b/">b</a>"#);
+    }
+
+    #[test]
+    fn requires_the_site_url_for_placeholders() {
+        assert!(parse(EXPORT, None).is_err());
+    }
+}
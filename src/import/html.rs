@@ -0,0 +1,383 @@
+use scraper::{ElementRef, Html, Node};
+
+use crate::{markdown::escape, plain_text::collapse_whitespace};
+
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "div",
+    "figcaption",
+    "figure",
+    "footer",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "main",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "ul",
+];
+
+/// Elements kept as raw HTML, as markdown has no equivalent for them.
+const RAW_ELEMENTS: &[&str] = &["audio", "iframe", "video"];
+
+/// Elements dropped altogether, with their content.
+const SKIPPED_ELEMENTS: &[&str] = &["noscript", "script", "style"];
+
+/// Converts the HTML body of a post to markdown.
+pub fn to_markdown(html: &str) -> String {
+    let fragment = Html::parse_fragment(html);
+    blocks(fragment.root_element()).join("\n\n")
+}
+
+/// Converts the children of `el` to a list of markdown blocks. Inline content
+/// between blocks becomes a paragraph.
+fn blocks(el: ElementRef) -> Vec<String> {
+    let mut res = vec![];
+    let mut paragraph = String::new();
+    for node in el.children() {
+        if let Some(child) = ElementRef::wrap(node) {
+            let name = child.value().name();
+            if BLOCK_ELEMENTS.contains(&name) || RAW_ELEMENTS.contains(&name) {
+                push_paragraph(&mut res, &mut paragraph);
+                res.extend(block(child));
+            } else {
+                paragraph.push_str(&inline(child));
+            }
+        } else if let Node::Text(text) = node.value() {
+            paragraph.push_str(&escape(&collapse_whitespace(text)));
+        }
+    }
+    push_paragraph(&mut res, &mut paragraph);
+    res
+}
+
+fn push_paragraph(blocks: &mut Vec<String>, paragraph: &mut String) {
+    let text = tidy_paragraph(paragraph);
+    if !text.is_empty() {
+        blocks.push(text);
+    }
+    paragraph.clear();
+}
+
+/// Trims the lines of a paragraph, which has more than one only after hard
+/// line breaks.
+fn tidy_paragraph(text: &str) -> String {
+    let text = text.lines().map(str::trim).collect::<Vec<_>>().join("\n");
+    escape_line_start(text.trim())
+}
+
+fn block(el: ElementRef) -> Vec<String> {
+    let name = el.value().name();
+    let text = match name {
+        "p" => tidy_paragraph(&inline_children(el)),
+        "figcaption" => {
+            let caption = inline_children(el);
+            let caption = caption.trim();
+            if caption.is_empty() {
+                String::new()
+            } else {
+                format!("*{caption}*")
+            }
+        }
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+            let level = usize::from(name.as_bytes()[1] - b'0');
+            format!("{} {}", "#".repeat(level), inline_children(el).trim())
+        }
+        "hr" => "---".to_string(),
+        "ul" => list(el, None),
+        "ol" => {
+            let start = el
+                .value()
+                .attr("start")
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(1);
+            list(el, Some(start))
+        }
+        "blockquote" => blocks(el)
+            .join("\n\n")
+            .lines()
+            .map(|line| {
+                if line.is_empty() {
+                    ">".to_string()
+                } else {
+                    format!("> {line}")
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        "pre" => code_block(el),
+        "table" => table(el),
+        name if RAW_ELEMENTS.contains(&name) => el.html(),
+        _ => return blocks(el),
+    };
+
+    if text.trim().is_empty() {
+        vec![]
+    } else {
+        vec![text]
+    }
+}
+
+fn list(el: ElementRef, start: Option<usize>) -> String {
+    el.children()
+        .filter_map(ElementRef::wrap)
+        .filter(|item| item.value().name() == "li")
+        .enumerate()
+        .map(|(i, item)| {
+            let marker = start.map_or_else(|| "-".to_string(), |start| format!("{}.", start + i));
+            let indent = " ".repeat(marker.len() + 1);
+
+            // nested lists stay attached to the item's text
+            let mut content = String::new();
+            for (j, block) in blocks(item).iter().enumerate() {
+                if j > 0 {
+                    let is_list =
+                        block.starts_with("- ") || block.starts_with(|c: char| c.is_ascii_digit());
+                    content.push_str(if is_list { "\n" } else { "\n\n" });
+                }
+                content.push_str(block);
+            }
+
+            let content = content
+                .lines()
+                .enumerate()
+                .map(|(j, line)| {
+                    if j == 0 || line.is_empty() {
+                        line.to_string()
+                    } else {
+                        format!("{indent}{line}")
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            format!("{marker} {content}")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn code_block(el: ElementRef) -> String {
+    let code = el.text().collect::<String>();
+    let code = code.trim_end_matches('\n');
+
+    let language = std::iter::once(el)
+        .chain(
+            el.children()
+                .filter_map(ElementRef::wrap)
+                .filter(|c| c.value().name() == "code"),
+        )
+        .flat_map(|e| e.value().classes())
+        .find_map(|class| {
+            class
+                .strip_prefix("language-")
+                .or_else(|| class.strip_prefix("lang-"))
+        })
+        .unwrap_or_default();
+
+    // the fence must be longer than any run of backticks in the code
+    let longest_run = code
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or_default();
+    let fence = "`".repeat(longest_run.max(2) + 1);
+    format!("{fence}{language}\n{code}\n{fence}")
+}
+
+fn table(el: ElementRef) -> String {
+    let rows = el
+        .descendants()
+        .filter_map(ElementRef::wrap)
+        .filter(|row| row.value().name() == "tr")
+        .map(|row| {
+            row.children()
+                .filter_map(ElementRef::wrap)
+                .filter(|cell| matches!(cell.value().name(), "td" | "th"))
+                .map(|cell| {
+                    inline_children(cell)
+                        .replace('|', "\\|")
+                        .replace("\\\n", " ")
+                        .trim()
+                        .to_string()
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let columns = rows.iter().map(Vec::len).max().unwrap_or_default();
+    if columns == 0 {
+        return String::new();
+    }
+
+    let row = |cells: &[String]| {
+        let cells = (0..columns)
+            .map(|i| cells.get(i).map_or("", String::as_str))
+            .collect::<Vec<_>>();
+        format!("| {} |", cells.join(" | "))
+    };
+    let mut lines = vec![row(&rows[0]), row(&vec!["---".to_string(); columns])];
+    lines.extend(rows[1..].iter().map(|cells| row(cells)));
+    lines.join("\n")
+}
+
+fn inline_children(el: ElementRef) -> String {
+    el.children()
+        .map(|node| {
+            if let Some(child) = ElementRef::wrap(node) {
+                inline(child)
+            } else if let Node::Text(text) = node.value() {
+                escape(&collapse_whitespace(text))
+            } else {
+                String::new()
+            }
+        })
+        .collect()
+}
+
+fn inline(el: ElementRef) -> String {
+    let name = el.value().name();
+    match name {
+        "strong" | "b" => wrap_inline(&inline_children(el), "**"),
+        "em" | "i" => wrap_inline(&inline_children(el), "*"),
+        "del" | "s" | "strike" => wrap_inline(&inline_children(el), "~~"),
+        "code" => {
+            let code = el.text().collect::<String>();
+            let delimiter = if code.contains('`') { "`` " } else { "`" };
+            format!(
+                "{delimiter}{code}{}",
+                delimiter.chars().rev().collect::<String>()
+            )
+        }
+        "br" => "\\\n".to_string(),
+        "img" => {
+            let src = el.value().attr("src").unwrap_or_default();
+            let alt = el.value().attr("alt").unwrap_or_default();
+            if src.is_empty() {
+                String::new()
+            } else {
+                format!("![{}]({})", escape(alt), link_destination(src))
+            }
+        }
+        "a" => {
+            let text = inline_children(el);
+            match el.value().attr("href") {
+                Some(href) if !href.is_empty() && !text.trim().is_empty() => {
+                    format!("[{}]({})", text.trim(), link_destination(href))
+                }
+                _ => text,
+            }
+        }
+        name if SKIPPED_ELEMENTS.contains(&name) => String::new(),
+        name if RAW_ELEMENTS.contains(&name) => el.html(),
+        _ => inline_children(el),
+    }
+}
+
+/// Wraps inline text in a delimiter, keeping the surrounding whitespace
+/// outside of it as `** bold**` would not be parsed as emphasis.
+fn wrap_inline(text: &str, delimiter: &str) -> String {
+    let trimmed = text.trim();
+    if trimmed.is_empty() {
+        return text.to_string();
+    }
+    let leading = &text[..text.len() - text.trim_start().len()];
+    let trailing = &text[text.trim_end().len()..];
+    format!("{leading}{delimiter}{trimmed}{delimiter}{trailing}")
+}
+
+fn link_destination(url: &str) -> String {
+    if url.contains(|c: char| c.is_whitespace() || c == '(' || c == ')') {
+        format!("<{url}>")
+    } else {
+        url.to_string()
+    }
+}
+
+/// Escapes the characters that would turn a paragraph into another block
+/// (a heading, a list, ...) when they start it.
+fn escape_line_start(text: &str) -> String {
+    let digits = text.chars().take_while(char::is_ascii_digit).count();
+    if text.starts_with(['#', '-', '+', '=', '|']) {
+        format!("\\{text}")
+    } else if digits > 0 && text[digits..].starts_with(['.', ')']) {
+        format!("{}\\{}", &text[..digits], &text[digits..])
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_blocks() {
+        assert_eq!(
+            to_markdown("<h2>Title</h2><p>Some <strong>bold </strong>text<br>here</p><hr>"),
+            "## Title\n\nSome **bold** text\\\nhere\n\n---"
+        );
+    }
+
+    #[test]
+    fn converts_lists_and_quotes() {
+        assert_eq!(
+            to_markdown(r#"<ol start="2"><li>a<ul><li>b</li></ul></li><li>c</li></ol>"#),
+            "2. a\n   - b\n3. c"
+        );
+        assert_eq!(
+            to_markdown("<blockquote><p>a</p><p>b</p></blockquote>"),
+            "> a\n>\n> b"
+        );
+    }
+
+    #[test]
+    fn converts_code() {
+        assert_eq!(
+            to_markdown(r#"<pre><code class="language-rust">let s = "```";</code></pre>"#),
+            "````rust\nlet s = \"```\";\n````"
+        );
+        assert_eq!(to_markdown("<p><code>a`b</code></p>"), "`` a`b ``");
+    }
+
+    #[test]
+    fn converts_links_and_images() {
+        assert_eq!(
+            to_markdown(r#"<p><a href="/a (b)">link</a> <img src="i.png" alt="[i]"></p>"#),
+            r"[link](</a (b)>) ![\[i\]](i.png)"
+        );
+    }
+
+    #[test]
+    fn converts_tables() {
+        assert_eq!(
+            to_markdown("<table><tr><th>a</th><th>b|c</th></tr><tr><td>1</td></tr></table>"),
+            "| a | b\\|c |\n| --- | --- |\n| 1 |  |"
+        );
+    }
+
+    #[test]
+    fn escapes_markdown() {
+        assert_eq!(
+            to_markdown("<p># 1. &lt;Tag&gt; *a* &amp; [b]</p><p>1. c</p>"),
+            "\\# 1. &lt;Tag\\> \\*a\\* &amp; \\[b\\]\n\n1\\. c"
+        );
+    }
+
+    #[test]
+    fn keeps_media_and_drops_scripts() {
+        assert_eq!(
+            to_markdown(r#"<p>a<script>b()</script></p><iframe src="/v"></iframe>"#),
+            "a\n\n<iframe src=\"/v\"></iframe>"
+        );
+    }
+}
@@ -0,0 +1,167 @@
+mod ghost;
+mod html;
+mod wordpress;
+
+use std::{collections::HashSet, error::Error, fs, path::Path};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use reqwest::Url;
+use scraper::{Html, Selector};
+
+use crate::{articles::ARTICLES_DIR, markdown::slugify, mdx::ComponentRegistry, validate};
+
+/// Where downloaded images are saved, one directory per article.
+const IMAGES_DIR: &str = "./static/imported";
+
+/// A post read from an export, before it is converted to markdown.
+pub struct ImportedPost {
+    pub slug: String,
+    pub title: String,
+    pub datetime: DateTime<Utc>,
+    pub html: String,
+}
+
+const USAGE: &str =
+    "usage: univrs import <ghost.json|wordpress.xml> [--download-images] [--site-url <url>]";
+
+/// Imports the posts of a Ghost JSON or WordPress WXR export as articles.
+///
+/// Articles that already exist are left untouched. With `--download-images`,
+/// remote images are saved under `static/` and linked from there.
+/// `--site-url` replaces the `__GHOST_URL__` placeholder of Ghost exports,
+/// and is required if any post links to it. Posts that aren't published or
+/// scheduled are skipped.
+///
+/// # Errors
+///
+/// Returns an error if the export can't be read or parsed, or if an article
+/// can't be written.
+pub async fn run(args: &[String], components: &ComponentRegistry) -> Result<(), Box<dyn Error>> {
+    let mut file = None;
+    let mut download_images = false;
+    let mut site_url = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--download-images" => download_images = true,
+            "--site-url" => site_url = Some(args.next().ok_or(USAGE)?.as_str()),
+            arg if arg.starts_with("--") || file.is_some() => return Err(USAGE.into()),
+            arg => file = Some(arg),
+        }
+    }
+    let file = file.ok_or(USAGE)?;
+
+    let input = fs::read_to_string(file).map_err(|err| format!("{file}: {err}"))?;
+    let posts = if input.trim_start().starts_with('{') {
+        ghost::parse(&input, site_url)?
+    } else {
+        wordpress::parse(&input)?
+    };
+
+    let mut imported = 0;
+    for post in posts {
+        let slug = slugify(&post.slug);
+        if slug.is_empty() {
+            tracing::warn!("skipping `{}`: it has no slug", post.title);
+            continue;
+        }
+        let path = Path::new(ARTICLES_DIR).join(format!("{slug}.md"));
+        if path.exists() {
+            tracing::warn!(
+                "skipping `{}`: {} already exists",
+                post.title,
+                path.display()
+            );
+            continue;
+        }
+
+        let mut markdown = html::to_markdown(&post.html);
+        if download_images {
+            for (remote, local) in download_post_images(&slug, &post.html).await {
+                markdown = markdown.replace(&remote, &local);
+            }
+        }
+
+        fs::write(&path, article_file(&post, &markdown))?;
+        tracing::info!("imported `{}` as {}", post.title, path.display());
+        imported += 1;
+    }
+    tracing::info!("imported {imported} article(s) into {ARTICLES_DIR}");
+
+    validate::check(components).await
+}
+
+fn article_file(post: &ImportedPost, markdown: &str) -> String {
+    // JSON strings are valid YAML strings, with the escaping done for us
+    let title = serde_json::to_string(&post.title).unwrap();
+    let datetime = post.datetime.to_rfc3339_opts(SecondsFormat::Millis, true);
+    format!("---\ntitle: {title}\ndatetime: {datetime}\n---\n\n{markdown}\n")
+}
+
+/// Downloads the remote images of a post. Returns the URLs that were
+/// downloaded, with the path to use instead. Failures are only logged, the
+/// image keeps pointing to the remote URL.
+async fn download_post_images(slug: &str, html: &str) -> Vec<(String, String)> {
+    let dom = Html::parse_fragment(html);
+    let selector = Selector::parse("img[src]").unwrap();
+    let urls = dom
+        .select(&selector)
+        .filter_map(|img| img.value().attr("src"))
+        .filter(|src| src.starts_with("http://") || src.starts_with("https://"))
+        .collect::<HashSet<_>>();
+
+    let dir = Path::new(IMAGES_DIR).join(slug);
+    let mut names = HashSet::new();
+    let mut res = vec![];
+    for url in urls {
+        let name = image_file_name(url, &names);
+        match download(url, &dir.join(&name)).await {
+            Ok(()) => {
+                res.push((url.to_string(), format!("/static/imported/{slug}/{name}")));
+                names.insert(name);
+            }
+            Err(err) => tracing::warn!("could not download {url}: {err}"),
+        }
+    }
+    res
+}
+
+/// Picks a file name for an image out of its URL, unique among `taken`.
+fn image_file_name(url: &str, taken: &HashSet<String>) -> String {
+    let name = Url::parse(url)
+        .ok()
+        .and_then(|url| {
+            url.path_segments()
+                .and_then(|mut segments| segments.next_back().map(str::to_string))
+        })
+        .map(|name| {
+            name.chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                        c
+                    } else {
+                        '_'
+                    }
+                })
+                .collect::<String>()
+        })
+        .filter(|name| !name.trim_matches(['.', '_']).is_empty())
+        .unwrap_or_else(|| "image".to_string());
+
+    let mut candidate = name.clone();
+    let mut i = 1;
+    while taken.contains(&candidate) {
+        candidate = format!("{i}-{name}");
+        i += 1;
+    }
+    candidate
+}
+
+async fn download(url: &str, path: &Path) -> Result<(), Box<dyn Error>> {
+    let bytes = reqwest::get(url).await?.error_for_status()?.bytes().await?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, bytes)?;
+    Ok(())
+}
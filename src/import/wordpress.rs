@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use quick_xml::{events::Event, Reader};
+
+use super::ImportedPost;
+
+/// Reads the posts of a WordPress WXR export. Pages, attachments, and posts
+/// that aren't published or scheduled or that have a password are skipped.
+pub fn parse(input: &str) -> Result<Vec<ImportedPost>, String> {
+    let mut reader = Reader::from_str(input);
+    let mut res = vec![];
+    // fields of the `<item>` being read, by tag name
+    let mut item: Option<HashMap<String, String>> = None;
+    let mut field: Option<String> = None;
+
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|err| format!("invalid WXR export: {err}"))?;
+        match event {
+            Event::Start(tag) => {
+                let name = String::from_utf8_lossy(tag.name().as_ref()).into_owned();
+                if name == "item" {
+                    item = Some(HashMap::new());
+                } else if item.is_some() {
+                    field = Some(name);
+                }
+            }
+            Event::Text(text) => {
+                if let (Some(item), Some(field)) = (&mut item, &field) {
+                    let text = text
+                        .unescape()
+                        .map_err(|err| format!("invalid WXR export: {err}"))?;
+                    item.entry(field.clone()).or_default().push_str(&text);
+                }
+            }
+            Event::CData(data) => {
+                if let (Some(item), Some(field)) = (&mut item, &field) {
+                    let data = data.into_inner();
+                    item.entry(field.clone())
+                        .or_default()
+                        .push_str(&String::from_utf8_lossy(&data));
+                }
+            }
+            Event::End(tag) => {
+                if tag.name().as_ref() == b"item" {
+                    if let Some(post) = item.take().as_ref().and_then(post_from_item) {
+                        res.push(post);
+                    }
+                }
+                field = None;
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(res)
+}
+
+fn post_from_item(item: &HashMap<String, String>) -> Option<ImportedPost> {
+    let field = |name: &str| item.get(name).map_or("", |v| v.trim());
+    if field("wp:post_type") != "post" {
+        return None;
+    }
+    let title = field("title").to_string();
+    let status = field("wp:status");
+    if !matches!(status, "publish" | "future") {
+        // trashed posts and automatic drafts aren't worth a warning
+        if !matches!(status, "trash" | "auto-draft" | "inherit") {
+            tracing::warn!("skipping `{title}`: it is not published (status `{status}`)");
+        }
+        return None;
+    }
+    if !field("wp:post_password").is_empty() {
+        tracing::warn!("skipping `{title}`: it is password protected");
+        return None;
+    }
+
+    let slug = match field("wp:post_name") {
+        "" => title.clone(),
+        slug => slug.to_string(),
+    };
+    // drafts have a zeroed GMT date
+    let datetime = NaiveDateTime::parse_from_str(field("wp:post_date_gmt"), "%Y-%m-%d %H:%M:%S")
+        .map(|date| date.and_utc())
+        .or_else(|_| DateTime::parse_from_rfc2822(field("pubDate")).map(|d| d.with_timezone(&Utc)))
+        .or_else(|_| {
+            NaiveDateTime::parse_from_str(field("wp:post_date"), "%Y-%m-%d %H:%M:%S")
+                .map(|date| date.and_utc())
+        })
+        .unwrap_or_else(|_| Utc::now());
+
+    Some(ImportedPost {
+        slug,
+        datetime,
+        html: autop(field("content:encoded")),
+        title,
+    })
+}
+
+/// WordPress stores the content of classic posts without paragraphs, adding
+/// them when rendering. Wraps blank-line separated chunks in `<p>` the same
+/// way, leaving alone the ones that already are blocks.
+fn autop(content: &str) -> String {
+    if content.contains("<p>") || content.contains("<!-- wp:") {
+        return content.to_string();
+    }
+
+    let content = content.replace("\r\n", "\n");
+    content
+        .split("\n\n")
+        .map(str::trim)
+        .filter(|chunk| !chunk.is_empty())
+        .map(|chunk| {
+            let is_block = [
+                "<p",
+                "<h",
+                "<ul",
+                "<ol",
+                "<pre",
+                "<blockquote",
+                "<div",
+                "<figure",
+                "<table",
+                "<hr",
+                "<iframe",
+            ]
+            .iter()
+            .any(|tag| chunk.starts_with(tag));
+            if is_block {
+                chunk.to_string()
+            } else {
+                format!("<p>{}</p>", chunk.replace('\n', "<br>\n"))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(status: &str, password: &str) -> String {
+        format!(
+            "<item><title>{status}</title><wp:post_type>post</wp:post_type>\
+             <wp:status>{status}</wp:status><wp:post_password>{password}</wp:post_password>\
+             <wp:post_date_gmt>2023-01-02 03:04:05</wp:post_date_gmt>\
+             <content:encoded><![CDATA[a\n\nb]]></content:encoded></item>"
+        )
+    }
+
+    #[test]
+    fn skips_unpublished_and_protected_posts() {
+        let export = format!(
+            "<rss><channel>{}{}{}{}{}</channel></rss>",
+            item("publish", ""),
+            item("future", ""),
+            item("draft", ""),
+            item("private", ""),
+            item("publish", "secret"),
+        );
+        let posts = parse(&export).unwrap();
+        let titles = posts.iter().map(|p| p.title.as_str()).collect::<Vec<_>>();
+        assert_eq!(titles, ["publish", "future"]);
+        assert_eq!(posts[0].html, "<p>a</p>\n<p>b</p>");
+        assert_eq!(posts[0].datetime.to_rfc3339(), "2023-01-02T03:04:05+00:00");
+    }
+}
@@ -1,102 +1,397 @@
-use std::fs::read_to_string;
+use std::{
+    collections::HashMap,
+    fmt::{self, Write},
+    fs::read_to_string,
+    path::{Path, PathBuf},
+};
 
-use rscx::{component, html, props};
+use comrak::{ComrakExtensionOptions, ComrakOptions};
+use rscx::{
+    component,
+    context::{expect_context, provide_context},
+    html,
+    html_escape::{decode_html_entities, encode_text},
+    props,
+};
 use rscx_mdx::mdx::{Mdx, MdxComponentProps};
+use scraper::{ElementRef, Html, Node, Selector};
 
-use crate::{
-    components::md::{Dialog},
-    images::{RemoteImg, Srcset},
-};
+use crate::{highlight::highlight_code_blocks, mdx::ComponentRegistry};
 
 pub struct MarkdownFile {
+    pub path: PathBuf,
     pub name: String,
+    pub source: String,
     pub content: String,
     pub frontmatter: frontmatter::Yaml,
 }
 
-pub fn load_dir(path: &str) -> Vec<MarkdownFile> {
-    std::fs::read_dir(path)
-        .unwrap()
+impl MarkdownFile {
+    /// Line of the source file where the markdown body starts.
+    #[must_use]
+    pub fn content_line(&self) -> usize {
+        let frontmatter_len = self.source.len() - self.content.len();
+        self.source[..frontmatter_len].lines().count() + 1
+    }
+}
+
+#[derive(Debug)]
+pub struct LoadError {
+    pub path: PathBuf,
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl LoadError {
+    pub fn new(path: &Path, message: impl Into<String>) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            line: None,
+            message: message.into(),
+        }
+    }
+
+    pub fn at(path: &Path, line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line: Some(line),
+            ..Self::new(path, message)
+        }
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}", self.path.display(), line, self.message),
+            None => write!(f, "{}: {}", self.path.display(), self.message),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+#[must_use]
+pub fn load_dir(path: &str) -> Vec<Result<MarkdownFile, LoadError>> {
+    let entries = match std::fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(err) => return vec![Err(LoadError::new(Path::new(path), err.to_string()))],
+    };
+
+    entries
         .filter_map(|entry| {
-            let entry = entry.unwrap();
-            let path = entry.path();
+            let path = match entry {
+                Ok(entry) => entry.path(),
+                Err(err) => return Some(Err(LoadError::new(Path::new(path), err.to_string()))),
+            };
             match path.extension() {
                 Some(ext) if ext == "md" => {}
                 _ => return None,
             }
 
-            let name = path.file_stem().unwrap().to_str().unwrap().to_string();
-            let input = read_to_string(path).unwrap();
-            let (frontmatter, content) = parse_frontmatter(&input);
-            Some(MarkdownFile {
-                name,
-                frontmatter,
-                content: content.to_string(),
-            })
+            Some(load_file(&path))
         })
         .collect::<Vec<_>>()
 }
 
-fn parse_frontmatter(input: &str) -> (frontmatter::Yaml, &str) {
-    let (fm, content) = frontmatter::parse_and_find_content(input).unwrap();
-    (fm.unwrap(), content)
+fn load_file(path: &Path) -> Result<MarkdownFile, LoadError> {
+    let name = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .ok_or_else(|| LoadError::new(path, "invalid file name"))?
+        .to_string();
+    let input = read_to_string(path).map_err(|err| LoadError::new(path, err.to_string()))?;
+    let (frontmatter, content) = parse_frontmatter(path, &input)?;
+    Ok(MarkdownFile {
+        path: path.to_path_buf(),
+        name,
+        frontmatter,
+        content: content.to_string(),
+        source: input,
+    })
 }
 
-async fn handler(name: String, props: MdxComponentProps) -> String {
-    match name.as_str() {
-        "Dialog" => {
-            let character = props.attributes.get("character").unwrap().clone().unwrap();
-            let pos = props.attributes.get("pos").unwrap().clone().unwrap();
-            html! {
-                <Dialog character=character pos=pos>
-                    {props.children}
-                </Dialog>
+fn parse_frontmatter<'a>(
+    path: &Path,
+    input: &'a str,
+) -> Result<(frontmatter::Yaml, &'a str), LoadError> {
+    let (fm, content) = frontmatter::parse_and_find_content(input).map_err(|err| {
+        // the yaml block starts on the second line of the file
+        let line = err.marker().line() + 1;
+        let message = err.to_string();
+        let message = message.split(" at line").next().unwrap_or_default();
+        LoadError::at(path, line, format!("invalid frontmatter: {message}"))
+    })?;
+    let fm = fm.ok_or_else(|| LoadError::at(path, 1, "missing frontmatter"))?;
+    Ok((fm, content))
+}
+
+#[props]
+pub struct MarkdownProps {
+    source: String,
+}
+
+/// Renders markdown with the MDX components of the [`ComponentRegistry`]
+/// in the context. A component that fails to render is replaced by the
+/// error.
+#[component]
+pub async fn Markdown(props: MarkdownProps) -> String {
+    let registry = expect_context::<ComponentRegistry>();
+    let handler = move |name: String, props: MdxComponentProps| {
+        let registry = registry.clone();
+        async move {
+            match registry.render(&name, props).await {
+                Ok(html) => html,
+                Err(err) => {
+                    tracing::error!("failed to render component: {err}");
+                    let message = encode_text(&err.to_string()).into_owned();
+                    html! {
+                        <p class="border border-black bg-yellow px-4 py-2 rounded-md">{message}</p>
+                    }
+                }
             }
         }
-        "RemoteImage" => {
-            let avif = props.attributes.get("avif").map(|v| v.clone().unwrap());
-            let webp = props.attributes.get("webp").map(|v| v.clone().unwrap());
-            let png = props.attributes.get("png").map(|v| v.clone().unwrap());
-            let jpeg = props.attributes.get("jpeg").map(|v| v.clone().unwrap());
-            let svg = props.attributes.get("svg").map(|v| v.clone().unwrap());
-            let srcset = Srcset {
-                avif,
-                webp,
-                png,
-                jpeg,
-                svg,
-            };
+    };
+    let html = html! {
+        <Mdx handler=handler source=highlight_code_blocks(&props.source) />
+    };
+    anchor_headings(&html)
+}
 
-            let alt = props
-                .attributes
-                .get("alt")
-                .map_or(String::new(), |v| v.clone().unwrap());
-            let class = props
-                .attributes
-                .get("alt")
-                .map_or(String::new(), |v| v.clone().unwrap());
-
-            let res = html! {
-                <RemoteImg srcset=srcset alt=alt class=class />
-            };
+/// The comrak options of the markdown renderer of rscx-mdx, for anything
+/// that parses articles on its own and should agree with the web pages.
+#[must_use]
+pub fn comrak_options() -> ComrakOptions {
+    ComrakOptions {
+        extension: ComrakExtensionOptions {
+            autolink: true,
+            table: true,
+            description_lists: true,
+            superscript: true,
+            strikethrough: true,
+            footnotes: true,
+            ..ComrakExtensionOptions::default()
+        },
+        ..ComrakOptions::default()
+    }
+}
+
+/// Renders markdown outside of a page, e.g. while loading articles, with
+/// `registry` in the context.
+pub async fn render(source: String, registry: ComponentRegistry) -> String {
+    rscx::axum::render(async move {
+        provide_context(registry);
+        html! {
+            <Markdown source=source />
+        }
+    })
+    .await
+    .0
+}
+
+/// Gives every heading an `id` derived from its text and a link to itself.
+/// Ids are unique within `html`: repeated titles get a numeric suffix.
+fn anchor_headings(html: &str) -> String {
+    let mut res = String::with_capacity(html.len());
+    let mut ids: HashMap<String, usize> = HashMap::new();
+    let mut rest = html;
+
+    while let Some(start) = rest.find("<h") {
+        let tag = &rest[start..];
+        let level = match tag.as_bytes().get(2) {
+            Some(l @ b'1'..=b'6')
+                if tag[3..].starts_with(|c: char| c == '>' || c.is_whitespace()) =>
+            {
+                *l as char
+            }
+            _ => {
+                res.push_str(&rest[..start + 2]);
+                rest = &rest[start + 2..];
+                continue;
+            }
+        };
+        let close = format!("</h{level}>");
+        let (Some(open_len), Some(close_start)) = (tag.find('>'), tag.find(&close)) else {
+            break;
+        };
+        let attributes = tag[3..open_len].trim();
+        if attributes.contains("id=") || close_start < open_len {
+            res.push_str(&rest[..=start + open_len]);
+            rest = &rest[start + open_len + 1..];
+            continue;
+        }
 
-            res
+        let inner = &tag[open_len + 1..close_start];
+        let mut id = slugify(&strip_tags(inner));
+        if id.is_empty() {
+            id = "section".to_string();
         }
-        _ => {
-            eprintln!("unknown component: {}", name);
-            String::new()
+        let count = ids.entry(id.clone()).or_default();
+        if *count > 0 {
+            id = format!("{id}-{count}");
         }
+        *count += 1;
+
+        let attributes = if attributes.is_empty() {
+            format!(r#"id="{id}""#)
+        } else {
+            format!(r#"{attributes} id="{id}""#)
+        };
+        res.push_str(&rest[..start]);
+        let _ = write!(
+            res,
+            r##"<h{level} {attributes}>{inner}<a class="heading-anchor" href="#{id}" aria-label="Link to this section">#</a>{close}"##
+        );
+        rest = &tag[close_start + close.len()..];
     }
+
+    res.push_str(rest);
+    res
 }
 
-#[props]
-pub struct MarkdownProps {
-    source: String,
+fn strip_tags(html: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    decode_html_entities(&text).into_owned()
 }
 
-#[component]
-pub async fn Markdown(props: MarkdownProps) -> String {
-    html! {
-        <Mdx handler=handler source=props.source />
+/// Escapes the characters that markdown would interpret. `<` becomes an
+/// entity, as MDX would otherwise take `<Name` for a component.
+#[must_use]
+pub fn escape(text: &str) -> String {
+    let mut res = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '<' => res.push_str("&lt;"),
+            '&' => res.push_str("&amp;"),
+            '\\' | '`' | '*' | '_' | '[' | ']' | '>' => {
+                res.push('\\');
+                res.push(c);
+            }
+            c => res.push(c),
+        }
+    }
+    res
+}
+
+#[must_use]
+pub fn slugify(s: &str) -> String {
+    s.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+#[derive(Clone, Debug)]
+pub struct TocEntry {
+    pub id: String,
+    pub title: String,
+    pub children: Vec<TocEntry>,
+}
+
+/// Builds a nested table of contents out of the `h2`-`h4` headings of a
+/// rendered markdown.
+///
+/// # Panics
+///
+/// Panics if the selector of the headings is invalid, which a test would
+/// catch.
+#[must_use]
+pub fn table_of_contents(html: &str) -> Vec<TocEntry> {
+    let dom = Html::parse_fragment(html);
+    let selector = Selector::parse("h2[id], h3[id], h4[id]").unwrap();
+
+    // (level, entry) of the headings that may still get children
+    let mut stack: Vec<(u8, TocEntry)> = vec![];
+    let mut toc = vec![];
+    for el in dom.select(&selector) {
+        let level = el.value().name().as_bytes()[1];
+        let title = heading_text(el);
+        let entry = TocEntry {
+            id: el.value().id().unwrap_or_default().to_string(),
+            title: title.split_whitespace().collect::<Vec<_>>().join(" "),
+            children: vec![],
+        };
+
+        while stack.last().is_some_and(|(l, _)| *l >= level) {
+            pop_toc_entry(&mut stack, &mut toc);
+        }
+        stack.push((level, entry));
+    }
+    while !stack.is_empty() {
+        pop_toc_entry(&mut stack, &mut toc);
+    }
+
+    toc
+}
+
+/// Text of a heading, without its link to itself.
+fn heading_text(el: ElementRef) -> String {
+    let mut text = String::new();
+    for node in el.children() {
+        if let Some(child) = ElementRef::wrap(node) {
+            if !child.value().classes().any(|c| c == "heading-anchor") {
+                text.push_str(&heading_text(child));
+            }
+        } else if let Node::Text(t) = node.value() {
+            text.push_str(t);
+        }
+    }
+    text
+}
+
+fn pop_toc_entry(stack: &mut Vec<(u8, TocEntry)>, toc: &mut Vec<TocEntry>) {
+    if let Some((_, entry)) = stack.pop() {
+        match stack.last_mut() {
+            Some((_, parent)) => parent.children.push(entry),
+            None => toc.push(entry),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn titles(toc: &[TocEntry]) -> Vec<(String, String, usize)> {
+        toc.iter()
+            .map(|e| (e.id.clone(), e.title.clone(), e.children.len()))
+            .collect()
+    }
+
+    #[test]
+    fn nests_headings() {
+        let html = anchor_headings("<h2>A</h2><h3>B</h3><h4>C</h4><h3>D</h3><h2>A</h2>");
+        let toc = table_of_contents(&html);
+        assert_eq!(
+            titles(&toc),
+            [("a".into(), "A".into(), 2), ("a-1".into(), "A".into(), 0)]
+        );
+        assert_eq!(
+            titles(&toc[0].children),
+            [("b".into(), "B".into(), 1), ("d".into(), "D".into(), 0)]
+        );
+        assert_eq!(titles(&toc[0].children[0].children)[0].1, "C");
+    }
+
+    #[test]
+    fn keeps_the_text_of_headings_only() {
+        let html = anchor_headings("<h2>Learning C#</h2><h3><code>Vec</code>\n  &amp; #</h3>");
+        let toc = table_of_contents(&html);
+        assert_eq!(toc[0].title, "Learning C#");
+        assert_eq!(toc[0].children[0].title, "Vec & #");
+    }
+
+    #[test]
+    fn skips_headings_without_ids() {
+        assert!(table_of_contents("<h1 id=\"a\">A</h1><h2>B</h2>").is_empty());
     }
 }
@@ -0,0 +1,313 @@
+use std::{
+    collections::HashMap, error::Error, fmt, future::Future, pin::Pin, str::FromStr, sync::Arc,
+};
+
+use rscx_mdx::mdx::MdxComponentProps;
+
+type RenderFuture = Pin<Box<dyn Future<Output = Result<String, RenderError>> + Send + Sync>>;
+type ValidateFuture = Pin<Box<dyn Future<Output = Result<(), String>> + Send + Sync>>;
+type TextFn = Box<dyn Fn(&Props) -> Text + Send + Sync>;
+
+/// The MDX components markdown can use, by name.
+///
+/// Modules add their components with a `register` function, and the
+/// registry is then handed to [`crate::markdown::Markdown`] through the
+/// context, and to the validation of articles.
+#[derive(Clone, Default)]
+pub struct ComponentRegistry {
+    components: HashMap<&'static str, Arc<Component>>,
+}
+
+impl ComponentRegistry {
+    pub fn register(&mut self, component: Component) {
+        self.components.insert(component.name, Arc::new(component));
+    }
+
+    /// Checks that `name` is a registered component, that `attributes` are
+    /// the ones it declares, and then runs its own validation, if any.
+    /// Returns a message for every problem found.
+    pub async fn check(
+        &self,
+        name: &str,
+        attributes: &HashMap<String, Option<String>>,
+    ) -> Vec<String> {
+        let Some(component) = self.components.get(name) else {
+            return vec![format!("unknown component `{name}`")];
+        };
+        let problems = component.check(attributes);
+        match &component.validate {
+            Some(validate) if problems.is_empty() => {
+                let props = Props {
+                    component: component.name,
+                    attributes: attributes.clone(),
+                    children: String::new(),
+                };
+                validate(props).await.err().into_iter().collect()
+            }
+            _ => problems,
+        }
+    }
+
+    /// Renders a component, after checking its attributes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the component is unknown, if its attributes are
+    /// invalid, or if it fails to render.
+    pub async fn render(
+        &self,
+        name: &str,
+        props: MdxComponentProps,
+    ) -> Result<String, RenderError> {
+        let Some(component) = self.components.get(name).cloned() else {
+            return Err(RenderError(format!("unknown component `{name}`")));
+        };
+        if let Some(problem) = component.check(&props.attributes).into_iter().next() {
+            return Err(RenderError(problem));
+        }
+
+        (component.render)(Props {
+            component: component.name,
+            attributes: props.attributes,
+            children: props.children,
+        })
+        .await
+    }
+
+    /// How the component `name` reads in the text versions of articles, or
+    /// `None` if there is no such component. Names are matched regardless
+    /// of case, as HTML parsers lowercase them.
+    #[must_use]
+    pub fn text(&self, name: &str, attributes: HashMap<String, Option<String>>) -> Option<Text> {
+        let component = self
+            .components
+            .values()
+            .find(|component| component.name.eq_ignore_ascii_case(name))?;
+        Some(match &component.text {
+            Some(text) => text(&Props {
+                component: component.name,
+                attributes,
+                children: String::new(),
+            }),
+            None => Text::Children,
+        })
+    }
+}
+
+impl fmt::Debug for ComponentRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.components.keys()).finish()
+    }
+}
+
+/// An MDX component: its name, the attributes it accepts and how to render
+/// it.
+pub struct Component {
+    name: &'static str,
+    attributes: Vec<Attribute>,
+    /// Groups of attributes of which at least one must be set.
+    one_of: Vec<&'static [&'static str]>,
+    render: Box<dyn Fn(Props) -> RenderFuture + Send + Sync>,
+    validate: Option<Box<dyn Fn(Props) -> ValidateFuture + Send + Sync>>,
+    text: Option<TextFn>,
+}
+
+/// How a component reads in the text versions of articles, plain text and
+/// gemtext, which can't render it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Text {
+    /// Its children, as they are.
+    Children,
+    /// Its children, on a line starting with a label, e.g. who is speaking.
+    Labelled(String),
+    /// Its children, after a title of their own.
+    Titled(String),
+    /// A link, instead of the component and its children.
+    Link { url: String, label: String },
+    /// An image, instead of the component and its children.
+    Image { src: String, alt: String },
+}
+
+struct Attribute {
+    name: &'static str,
+    required: bool,
+    /// Checks that a value parses as the type of the attribute.
+    parse: fn(&str) -> Result<(), String>,
+}
+
+impl Component {
+    pub fn new<F, Fut>(name: &'static str, render: F) -> Self
+    where
+        F: Fn(Props) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String, RenderError>> + Send + Sync + 'static,
+    {
+        Self {
+            name,
+            attributes: vec![],
+            one_of: vec![],
+            render: Box::new(move |props| Box::pin(render(props))),
+            validate: None,
+            text: None,
+        }
+    }
+
+    /// Declares an attribute that must be set, and parse as `T`.
+    #[must_use]
+    pub fn required<T>(self, name: &'static str) -> Self
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        self.attribute::<T>(name, true)
+    }
+
+    /// Declares an attribute that, when set, must parse as `T`.
+    #[must_use]
+    pub fn optional<T>(self, name: &'static str) -> Self
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        self.attribute::<T>(name, false)
+    }
+
+    /// Requires at least one of `names`, which must also be declared.
+    #[must_use]
+    pub fn one_of(mut self, names: &'static [&'static str]) -> Self {
+        self.one_of.push(names);
+        self
+    }
+
+    /// Validates the component beyond its attributes, e.g. that what it
+    /// refers to exists. Only run when the attributes are valid, and given
+    /// no children.
+    #[must_use]
+    pub fn validate<F, Fut>(mut self, validate: F) -> Self
+    where
+        F: Fn(Props) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), String>> + Send + Sync + 'static,
+    {
+        self.validate = Some(Box::new(move |props| Box::pin(validate(props))));
+        self
+    }
+
+    /// Sets how the component reads in plain text and gemtext, given its
+    /// attributes, checked, but no children. Defaults to its children.
+    #[must_use]
+    pub fn text<F>(mut self, text: F) -> Self
+    where
+        F: Fn(&Props) -> Text + Send + Sync + 'static,
+    {
+        self.text = Some(Box::new(text));
+        self
+    }
+
+    fn attribute<T>(mut self, name: &'static str, required: bool) -> Self
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        self.attributes.push(Attribute {
+            name,
+            required,
+            parse: |value| {
+                value
+                    .parse::<T>()
+                    .map(|_| ())
+                    .map_err(|err| err.to_string())
+            },
+        });
+        self
+    }
+
+    fn check(&self, attributes: &HashMap<String, Option<String>>) -> Vec<String> {
+        let mut problems = vec![];
+        for attr in &self.attributes {
+            match attributes.get(attr.name) {
+                Some(Some(value)) => {
+                    if let Err(err) = (attr.parse)(value) {
+                        problems.push(format!(
+                            "invalid `{}` for `{}`: {err}",
+                            attr.name, self.name
+                        ));
+                    }
+                }
+                _ if attr.required => problems.push(format!(
+                    "missing attribute `{}` for `{}`",
+                    attr.name, self.name
+                )),
+                _ => {}
+            }
+        }
+        for names in &self.one_of {
+            if !names
+                .iter()
+                .any(|name| matches!(attributes.get(*name), Some(Some(_))))
+            {
+                problems.push(format!(
+                    "missing attribute for `{}`: at least one of {} is required",
+                    self.name,
+                    names.join(", ")
+                ));
+            }
+        }
+        problems
+    }
+}
+
+/// What a component is rendered with: its attributes, already checked
+/// against the ones it declares, and its rendered children.
+pub struct Props {
+    component: &'static str,
+    attributes: HashMap<String, Option<String>>,
+    pub children: String,
+}
+
+impl Props {
+    /// Parses a required attribute.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the attribute is missing or can't be parsed.
+    pub fn get<T>(&self, name: &str) -> Result<T, RenderError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        self.get_optional(name)?.ok_or_else(|| {
+            RenderError(format!(
+                "missing attribute `{name}` for `{}`",
+                self.component
+            ))
+        })
+    }
+
+    /// Parses an optional attribute.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the attribute is set but can't be parsed.
+    pub fn get_optional<T>(&self, name: &str) -> Result<Option<T>, RenderError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        match self.attributes.get(name) {
+            Some(Some(value)) => value.parse().map(Some).map_err(|err| {
+                RenderError(format!("invalid `{name}` for `{}`: {err}", self.component))
+            }),
+            _ => Ok(None),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct RenderError(pub String);
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Error for RenderError {}
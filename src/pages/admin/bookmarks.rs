@@ -6,7 +6,13 @@ use rscx::{context::provide_context, html};
 use scraper::{Html, Selector};
 use serde::Deserialize;
 
-use crate::{bookmarks::BookmarksRepo, components::layout::Layout, meta::render_with_meta};
+use crate::{
+    bookmarks::{BookmarksRepo, NewBookmark},
+    components::layout::Layout,
+    meta::render_with_meta,
+    search::SearchIndex,
+    tags::parse_tags,
+};
 
 use crate::pages::auth::{AuthContext, RequireAdmin};
 
@@ -68,6 +74,7 @@ pub async fn form(
                     <input type="url" name="favicon" placeholder="Favicon URL" value={meta.favicon} />
                     <input type="url" name="image" placeholder="Image URL" value={meta.image} />
                     <input type="date" name="posted_at" placeholder="Posted at" value={meta.published_at} />
+                    <input type="text" name="tags" placeholder="Tags (comma-separated)" />
                     <button type="submit">Save</button>
                 </form>
             }
@@ -170,11 +177,14 @@ pub struct BookmarkSubmit {
     favicon: String,
     image: String,
     posted_at: String,
+    #[serde(default)]
+    tags: String,
 }
 
 pub async fn submit(
     RequireAdmin(_): RequireAdmin,
     Extension(bookmarks_repo): Extension<BookmarksRepo>,
+    Extension(search_index): Extension<SearchIndex>,
     Form(BookmarkSubmit {
         slug,
         url,
@@ -183,6 +193,7 @@ pub async fn submit(
         favicon,
         image,
         posted_at,
+        tags,
     }): Form<BookmarkSubmit>,
 ) -> impl IntoResponse {
     render_with_meta(
@@ -196,17 +207,20 @@ pub async fn submit(
             let d = chrono::DateTime::from_utc(d, chrono::Utc);
 
             bookmarks_repo
-                .add(
-                    &slug,
-                    &url,
-                    &title,
-                    &description,
-                    (!favicon.is_empty()).then(|| favicon.as_str()),
-                    (!image.is_empty()).then(|| image.as_str()),
-                    &d,
-                )
+                .add(&NewBookmark {
+                    slug: &slug,
+                    url: &url,
+                    title: &title,
+                    description: &description,
+                    favicon: (!favicon.is_empty()).then_some(favicon.as_str()),
+                    image: (!image.is_empty()).then_some(image.as_str()),
+                    posted_at: d,
+                    tags: &parse_tags(&tags),
+                })
                 .await
                 .unwrap();
+            search_index.index_bookmarks(&bookmarks_repo.list().await);
+
             html! {
                 <p>big success</p>
                 <p>
@@ -1 +1,2 @@
 pub mod bookmarks;
+pub mod redirects;
@@ -0,0 +1,156 @@
+use axum::{
+    http,
+    response::IntoResponse,
+    routing::{get, post},
+    Extension, Form, Router,
+};
+use rscx::{context::provide_context, html, html_escape::encode_text, CollectFragment};
+use serde::Deserialize;
+
+use crate::{
+    components::layout::Layout,
+    meta::render_with_meta,
+    redirects::{normalize_path, Redirect, RedirectsRepo},
+};
+
+use crate::pages::auth::{AuthContext, RequireAdmin};
+
+pub fn register(r: Router) -> Router {
+    r.route("/redirects", get(redirects_handler))
+        .route("/redirects/add", post(add))
+        .route("/redirects/delete", post(delete))
+}
+
+pub async fn redirects_handler(
+    uri: http::Uri,
+    RequireAdmin(_): RequireAdmin,
+    Extension(auth): Extension<AuthContext>,
+    Extension(redirects_repo): Extension<RedirectsRepo>,
+) -> impl IntoResponse {
+    let redirects = redirects_repo.list().await;
+    render_with_meta(
+        || {
+            provide_context(uri);
+            provide_context(auth);
+        },
+        || async move {
+            html! {
+            <Layout title="Redirects - Antonio Pitasi">
+               <div class="flex flex-col w-full">
+                   <form class="flex flex-col" hx-post="./redirects/add" hx-target="#redirects">
+                       <input type="text" name="from_path" placeholder="From path (e.g. /old/path)" />
+                       <input type="text" name="to_path" placeholder="To path" />
+                       <button type="submit">Add redirect</button>
+                   </form>
+                   <div id="redirects">
+                       {redirects_table(&redirects, None)}
+                   </div>
+               </div>
+            </Layout>
+            }
+        },
+    )
+    .await
+}
+
+fn redirects_table(redirects: &[Redirect], error: Option<&str>) -> String {
+    let error = error.map_or_else(String::new, |error| {
+        html! {
+            <p class="font-semibold" role="alert">{error}</p>
+        }
+    });
+    let rows = redirects
+        .iter()
+        .map(|r| {
+            html! {
+                <tr>
+                    <td>{encode_text(&r.from_path)}</td>
+                    <td>{encode_text(&r.to_path)}</td>
+                    <td>
+                        <form hx-post="./redirects/delete" hx-target="#redirects">
+                            <input type="hidden" name="from_path" value={&r.from_path} />
+                            <button type="submit">Delete</button>
+                        </form>
+                    </td>
+                </tr>
+            }
+        })
+        .collect_fragment();
+
+    html! {
+        {error}
+        <table>
+            <thead>
+                <tr>
+                    <th>From</th>
+                    <th>To</th>
+                    <th></th>
+                </tr>
+            </thead>
+            <tbody>{rows}</tbody>
+        </table>
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RedirectSubmit {
+    from_path: String,
+    to_path: String,
+}
+
+/// Adds a redirect, unless the form is invalid, and answers with the
+/// updated table.
+///
+/// # Panics
+///
+/// Panics if the redirect can't be stored.
+pub async fn add(
+    RequireAdmin(_): RequireAdmin,
+    Extension(redirects_repo): Extension<RedirectsRepo>,
+    Form(RedirectSubmit { from_path, to_path }): Form<RedirectSubmit>,
+) -> impl IntoResponse {
+    let to_path = to_path.trim();
+    let error = if !from_path.trim().starts_with('/') {
+        Some("the path to redirect must start with /")
+    } else if !to_path.starts_with('/') {
+        Some("the redirect target must start with /")
+    } else if to_path.starts_with("//") || to_path.contains('\\') {
+        // browsers take both for a URL on another host
+        Some("the redirect target must be a path on this site")
+    } else if http::HeaderValue::from_str(to_path).is_err() {
+        Some("the redirect target is not a valid header value")
+    } else if normalize_path(&from_path) == normalize_path(to_path) {
+        Some("a path cannot redirect to itself")
+    } else {
+        redirects_repo.add(&from_path, to_path).await.unwrap();
+        None
+    };
+
+    let redirects = redirects_repo.list().await;
+    render_with_meta(
+        || {},
+        move || async move { redirects_table(&redirects, error) },
+    )
+    .await
+}
+
+#[derive(Deserialize)]
+pub struct RedirectDelete {
+    from_path: String,
+}
+
+/// Removes a redirect and answers with the updated table.
+///
+/// # Panics
+///
+/// Panics if the redirect can't be removed.
+pub async fn delete(
+    RequireAdmin(_): RequireAdmin,
+    Extension(redirects_repo): Extension<RedirectsRepo>,
+    Form(RedirectDelete { from_path }): Form<RedirectDelete>,
+) -> impl IntoResponse {
+    redirects_repo.delete(&from_path).await.unwrap();
+
+    let redirects = redirects_repo.list().await;
+    render_with_meta(|| {}, || async move { redirects_table(&redirects, None) }).await
+}
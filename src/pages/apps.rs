@@ -7,7 +7,11 @@ use rscx::{
 
 use crate::{
     apps::{App, AppsRepo},
-    components::layout::{Header, Layout, SecondarySidebar, SidebarNavItem},
+    components::{
+        layout::{Header, Layout, SecondarySidebar, SidebarNavItem},
+        tags::TagList,
+    },
+    export::Export,
     icons::{Heart, Link},
     images::Image,
     meta::render_with_meta,
@@ -17,12 +21,14 @@ use super::auth::AuthContext;
 
 pub async fn handler(
     uri: http::Uri,
+    export: Export,
     Extension(auth): Extension<AuthContext>,
     Extension(apps_repo): Extension<AppsRepo>,
 ) -> impl IntoResponse {
     render_with_meta(
         || {
             provide_context(uri);
+            provide_context(export);
             provide_context(auth);
             provide_context(apps_repo);
         },
@@ -39,6 +45,7 @@ pub async fn handler(
 
 pub async fn handler_app(
     uri: http::Uri,
+    export: Export,
     Extension(auth): Extension<AuthContext>,
     Extension(apps_repo): Extension<AppsRepo>,
     Path(slug): Path<String>,
@@ -48,6 +55,7 @@ pub async fn handler_app(
     render_with_meta(
         || {
             provide_context(uri);
+            provide_context(export);
             provide_context(auth);
             provide_context(apps_repo);
         },
@@ -134,6 +142,8 @@ fn AppContent(props: AppContentProps) -> String {
                         </h1>
                     </div>
 
+                    <TagList tags=props.app.tags />
+
                     <div class="typography">
                         {props.app.description}
                     </div>
@@ -1,36 +1,43 @@
-use atom_syndication::{
-    ContentBuilder, EntryBuilder, FeedBuilder, LinkBuilder, PersonBuilder, TextBuilder,
-};
 use axum::{
-    extract::Path,
+    extract::{Path, Query},
     http,
     response::{IntoResponse, Response},
     Extension,
 };
+use chrono::{DateTime, FixedOffset};
 use rscx::{
     component,
     context::{expect_context, provide_context},
-    html, props, CollectFragmentAsync,
+    html, props, CollectFragment, CollectFragmentAsync,
 };
 
 use crate::{
-    articles::{Article, ArticlesRepo},
-    components::layout::{
-        Header, Layout, MetaOGImage, SecondarySidebar, SidebarNavItem,
+    articles::{Article, ArticlesRepo, Series},
+    components::{
+        layout::{Header, Layout, MetaOGImage, SecondarySidebar, SidebarNavItem},
+        tags::TagList,
     },
+    conditional::{conditional, etag, not_modified_since},
+    epub,
+    export::Export,
+    feeds::{ArticlesFeed, FeedFormat, FeedQuery},
+    markdown::TocEntry,
     meta::{render_with_meta, Dedup},
+    plain_text,
 };
 
-use super::auth::AuthContext;
+use super::auth::{AuthContext, RequireAdmin};
 
 pub async fn page_articles(
     uri: http::Uri,
+    export: Export,
     Extension(auth): Extension<AuthContext>,
     Extension(articles_repo): Extension<ArticlesRepo>,
 ) -> impl IntoResponse {
     render_with_meta(
         || {
             provide_context(uri);
+            provide_context(export);
             provide_context(auth);
             provide_context(articles_repo);
         },
@@ -44,55 +51,88 @@ pub async fn page_articles(
     ).await
 }
 
-pub async fn articles_rss(Extension(articles_repo): Extension<ArticlesRepo>) -> impl IntoResponse {
-    let articles = articles_repo.list();
-    let last_modified = articles.first().unwrap().datetime.clone();
+fn articles_feed(
+    articles_repo: &ArticlesRepo,
+    format: FeedFormat,
+    page: Option<usize>,
+    export: &Export,
+    headers: &http::HeaderMap,
+) -> Response {
+    ArticlesFeed {
+        title: "Antonio Pitasi's Articles",
+        url: "https://anto.pt/articles",
+        articles: &articles_repo.list(),
+        page: page.unwrap_or(1),
+        // static exports only have the first page
+        paged: !export.0,
+        loaded_at: articles_repo.loaded_at(),
+    }
+    .into_response(format, headers)
+}
 
-    let entries = articles
-        .iter()
-        .take(10)
-        .map(|article| {
-            let url = format!("https://anto.pt/articles/{}", article.slug);
-            EntryBuilder::default()
-                .title(TextBuilder::default().value(article.title.clone()).build())
-                .id(url.clone())
-                .links(vec![LinkBuilder::default()
-                    .rel("alternate".to_string())
-                    .mime_type(Some("text/html".to_string()))
-                    .href(url)
-                    .build()])
-                .published(Some(article.datetime.clone()))
-                .updated(article.datetime.clone())
-                .content(Some(
-                    ContentBuilder::default()
-                        .content_type(Some("html".to_string()))
-                        .value(Some(article.content.clone()))
-                        .build(),
-                ))
-                .build()
-        })
-        .collect::<Vec<_>>();
-
-    let feed = FeedBuilder::default()
-        .title("Antonio Pitasi's Articles")
-        .id("https://anto.pt/articles".to_string())
-        .links(vec![
-            LinkBuilder::default()
-                .rel("self".to_string())
-                .href("https://anto.pt/articles/atom.xml".to_string())
-                .build(),
-            LinkBuilder::default()
-                .href("https://anto.pt/articles".to_string())
-                .build(),
-        ])
-        .authors(vec![PersonBuilder::default()
-            .name("Antonio Pitasi".to_string())
-            .build()])
-        .updated(last_modified)
-        .entries(entries)
-        .build();
-
-    feed.to_string()
+pub async fn articles_atom(
+    headers: http::HeaderMap,
+    Extension(articles_repo): Extension<ArticlesRepo>,
+    Query(query): Query<FeedQuery>,
+    export: Export,
+) -> Response {
+    articles_feed(
+        &articles_repo,
+        FeedFormat::Atom,
+        query.page,
+        &export,
+        &headers,
+    )
+}
+
+pub async fn articles_rss(
+    headers: http::HeaderMap,
+    Extension(articles_repo): Extension<ArticlesRepo>,
+    Query(query): Query<FeedQuery>,
+    export: Export,
+) -> Response {
+    articles_feed(
+        &articles_repo,
+        FeedFormat::Rss,
+        query.page,
+        &export,
+        &headers,
+    )
+}
+
+pub async fn articles_json(
+    headers: http::HeaderMap,
+    Extension(articles_repo): Extension<ArticlesRepo>,
+    Query(query): Query<FeedQuery>,
+    export: Export,
+) -> Response {
+    articles_feed(
+        &articles_repo,
+        FeedFormat::Json,
+        query.page,
+        &export,
+        &headers,
+    )
+}
+
+/// Serves the feed in the format preferred by the `Accept` header.
+pub async fn articles_negotiated_feed(
+    headers: http::HeaderMap,
+    Extension(articles_repo): Extension<ArticlesRepo>,
+    Query(query): Query<FeedQuery>,
+    export: Export,
+) -> Response {
+    let accept = headers
+        .get(http::header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    articles_feed(
+        &articles_repo,
+        FeedFormat::from_accept(accept),
+        query.page,
+        &export,
+        &headers,
+    )
 }
 
 #[props]
@@ -116,6 +156,7 @@ pub fn Articles(props: ArticlesProps) -> String {
                     </span>
                     <span class="opacity-60">
                         {article.datetime.format("%B %d, %Y").to_string()}
+                        " · " {article.reading_time} " min read"
                     </span>
                 </div>
             };
@@ -139,10 +180,22 @@ pub fn Articles(props: ArticlesProps) -> String {
         html! {}
     };
 
+    let feeds = FeedFormat::ALL
+        .into_iter()
+        .map(|format| async move {
+            let title = format!("{} Feed for anto.pt articles", format.label());
+            let href = format!("/articles/{}", format.file_name());
+            html! {
+                <Dedup id=format!("feed-{}", format.file_name())>
+                    <link rel="alternate" type=format.content_type() title=title href=href />
+                </Dedup>
+            }
+        })
+        .collect_fragment_async()
+        .await;
+
     html! {
-        <Dedup id="atom".to_string()>
-            <link rel="alternate" type="application/atom+xml" title="RSS Feed for anto.pt articles" href="/articles/atom.xml" />
-        </Dedup>
+        {feeds}
         <div class="relative h-full w-full flex-row lg:grid lg:grid-cols-[20rem_minmax(0,1fr)]">
             <SecondarySidebar title="Articles".into()>
                 {v}
@@ -154,23 +207,49 @@ pub fn Articles(props: ArticlesProps) -> String {
 
 pub async fn page_article(
     uri: http::Uri,
+    export: Export,
+    headers: http::HeaderMap,
     Extension(auth): Extension<AuthContext>,
     Extension(articles_repo): Extension<ArticlesRepo>,
+    admin: Option<RequireAdmin>,
     Path(slug): Path<String>,
 ) -> Response {
-    let article = articles_repo.get_article_by_slug(&slug);
+    if let Some(slug) = slug.strip_suffix(".epub") {
+        return article_epub(&headers, &articles_repo, slug).await;
+    }
+    if let Some(slug) = slug.strip_suffix(".md") {
+        return article_source(&headers, &articles_repo, slug);
+    }
+    if let Some(slug) = slug.strip_suffix(".txt") {
+        return article_text(&headers, &articles_repo, slug).await;
+    }
+
+    let article = if admin.is_some() {
+        articles_repo.get_scheduled_article_by_slug(&slug)
+    } else {
+        articles_repo.get_article_by_slug(&slug)
+    };
     if article.is_none() {
         return (http::StatusCode::NOT_FOUND, "404 not found").into_response();
     }
-    let article = article.unwrap().clone();
+    let article = article.unwrap();
+
+    let series = article
+        .series
+        .as_ref()
+        .map(|s| articles_repo.list_series(&s.slug))
+        .unwrap_or_default();
+    let related = articles_repo.related(&article);
+    let backlinks = articles_repo.backlinks(&article);
     let title = format!("{} - Antonio Pitasi", article.title.clone());
     let og_image = format!(
         "https://anto.pt/articles/{}/social-image.png",
         article.slug.clone()
     );
 
-    render_with_meta(|| {
+    let page = render_with_meta(|| {
         provide_context(uri);
+        provide_context(export);
         provide_context(auth);
         provide_context(articles_repo);
     }, || async {
@@ -179,42 +258,399 @@ pub async fn page_article(
                 <MetaOGImage content=og_image />
             }>
                 <Articles>
-                    <ArticleContent a=article />
+                    <ArticleContent a=article series=series related=related backlinks=backlinks />
                 </Articles>
             </Layout>
         }
     })
-    .await
-    .into_response()
+    .await;
+
+    // the page depends on likes and on who is logged in too, which no date
+    // tracks: it is validated by its ETag only
+    conditional(&headers, &etag(page.0.as_bytes()), None, page)
+}
+
+async fn article_epub(
+    headers: &http::HeaderMap,
+    articles_repo: &ArticlesRepo,
+    slug: &str,
+) -> Response {
+    let Some(article) = articles_repo.get_article_by_slug(slug) else {
+        return (http::StatusCode::NOT_FOUND, "404 not found").into_response();
+    };
+    let last_modified = articles_repo.loaded_at().max(article.datetime);
+    if let Some(res) = not_modified_since(headers, last_modified) {
+        return res;
+    }
+
+    let book = articles_repo
+        .epub(&format!("articles/{slug}"), || async {
+            let identifier = format!("https://anto.pt/articles/{slug}");
+            epub::build(&article.title, &identifier, std::slice::from_ref(&article)).await
+        })
+        .await;
+    epub_response(headers, &format!("{slug}.epub"), last_modified, book)
+}
+
+/// Serves the markdown an article was written in, frontmatter included.
+fn article_source(headers: &http::HeaderMap, articles_repo: &ArticlesRepo, slug: &str) -> Response {
+    let Some(article) = articles_repo.get_article_by_slug(slug) else {
+        return (http::StatusCode::NOT_FOUND, "404 not found").into_response();
+    };
+    text_response(
+        headers,
+        "text/markdown; charset=utf-8",
+        articles_repo.loaded_at().max(article.datetime),
+        article.source,
+    )
+}
+
+async fn article_text(
+    headers: &http::HeaderMap,
+    articles_repo: &ArticlesRepo,
+    slug: &str,
+) -> Response {
+    let Some(article) = articles_repo.get_article_by_slug(slug) else {
+        return (http::StatusCode::NOT_FOUND, "404 not found").into_response();
+    };
+    let last_modified = articles_repo.loaded_at().max(article.datetime);
+    if let Some(res) = not_modified_since(headers, last_modified) {
+        return res;
+    }
+
+    let text = plain_text::render(&article, articles_repo.components()).await;
+    text_response(headers, "text/plain; charset=utf-8", last_modified, text)
+}
+
+fn text_response(
+    headers: &http::HeaderMap,
+    content_type: &'static str,
+    last_modified: DateTime<FixedOffset>,
+    body: String,
+) -> Response {
+    conditional(
+        headers,
+        &etag(body.as_bytes()),
+        Some(last_modified),
+        ([(http::header::CONTENT_TYPE, content_type)], body),
+    )
+}
+
+/// Serves every article as a single EPUB book, oldest first.
+///
+/// The book only changes when the articles do: it is built again after a
+/// reload of the repo, or once a scheduled article gets published.
+pub async fn archive_epub(
+    headers: http::HeaderMap,
+    Extension(articles_repo): Extension<ArticlesRepo>,
+) -> Response {
+    let mut articles = articles_repo.list();
+    let Some(last_modified) = articles
+        .first()
+        .map(|a| articles_repo.loaded_at().max(a.datetime))
+    else {
+        return (http::StatusCode::NOT_FOUND, "404 not found").into_response();
+    };
+    if let Some(res) = not_modified_since(&headers, last_modified) {
+        return res;
+    }
+
+    // scheduled articles get published between reloads too
+    let key = format!("archive/{}", articles.len());
+    let book = articles_repo
+        .epub(&key, || async {
+            articles.reverse();
+            epub::build(
+                "Antonio Pitasi's Articles",
+                "https://anto.pt/articles/archive.epub",
+                &articles,
+            )
+            .await
+        })
+        .await;
+    epub_response(&headers, "anto.pt-articles.epub", last_modified, book)
+}
+
+fn epub_response(
+    headers: &http::HeaderMap,
+    file_name: &str,
+    last_modified: DateTime<FixedOffset>,
+    book: Vec<u8>,
+) -> Response {
+    conditional(
+        headers,
+        &etag(&book),
+        Some(last_modified),
+        (
+            [
+                (
+                    http::header::CONTENT_TYPE,
+                    "application/epub+zip".to_string(),
+                ),
+                (
+                    http::header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"{file_name}\""),
+                ),
+            ],
+            book,
+        ),
+    )
 }
 
 #[props]
 pub struct ArticleContentProps {
     a: Article,
+    /// Every part of the series the article belongs to, in order.
+    #[builder(default)]
+    series: Vec<Article>,
+    #[builder(default)]
+    related: Vec<Article>,
+    #[builder(default)]
+    backlinks: Vec<Article>,
 }
 
 #[component]
 fn ArticleContent(props: ArticleContentProps) -> String {
+    let preview = if props.a.is_published() {
+        String::new()
+    } else {
+        html! {
+            <p class="rounded-md border border-black bg-jasmine px-4 py-2">
+                "Preview: this article is scheduled for "
+                {props.a.publish_at.format("%B %d, %Y at %H:%M").to_string()}
+                " and is not visible to readers yet."
+            </p>
+        }
+    };
+
+    let toc = if props.a.is_long() {
+        html! {
+            <aside class="hidden xl:block sticky top-20 h-fit w-56 shrink-0 text-sm">
+                <p class="font-semibold">"On this page"</p>
+                <nav class="toc">
+                    {render_toc(&props.a.toc)}
+                </nav>
+            </aside>
+        }
+    } else {
+        String::new()
+    };
+
+    let epub_link = if props.a.is_published() {
+        let href = format!("/articles/{}.epub", props.a.slug);
+        html! {
+            <a href=href>"EPUB"</a>
+        }
+    } else {
+        String::new()
+    };
+
+    let current = props.series.iter().position(|a| a.slug == props.a.slug);
+    let (series_box, series_nav) = match (&props.a.series, current) {
+        (Some(series), Some(current)) => (
+            html! {
+                <SeriesBox series=series.clone() parts=props.series.clone() current=current />
+            },
+            html! {
+                <SeriesNav parts=props.series current=current />
+            },
+        ),
+        _ => (String::new(), String::new()),
+    };
+
     html! {
         <main class="typography relative min-h-full bg-floralwhite pb-24 lg:pb-0">
             <Header title=props.a.title.clone() />
-            <article class="w-full bg-floralwhite p-8">
-                <div class="mx-auto max-w-2xl">
+            <article class="flex w-full flex-row justify-center gap-8 bg-floralwhite p-8">
+                <div class="min-w-0 max-w-2xl flex-1">
                     <div class="flex flex-col gap-3">
                         <h1 class="title font-neu font-semibold text-darkviolet text-4xl">
                             {props.a.title}
                         </h1>
-                        <div class="flex flex-row">
+                        <div class="flex flex-row gap-2">
                             <span class="text-gray-500">
-                                "Written on " {props.a.datetime.format("%B %d, %Y").to_string()} "."
+                                "Written on " {props.a.datetime.format("%B %d, %Y").to_string()} ". "
+                                {props.a.reading_time} " min read."
                             </span>
+                            {epub_link}
                         </div>
+                        <TagList tags=props.a.tags />
+                        {preview}
+                        {series_box}
                     </div>
                     <div class="mt-4">
                         {props.a.content}
                     </div>
+                    {series_nav}
+                    <Backlinks articles=props.backlinks />
+                    <RelatedArticles articles=props.related />
                 </div>
+                {toc}
             </article>
         </main>
     }
 }
+
+#[props]
+pub struct SeriesBoxProps {
+    series: Series,
+    parts: Vec<Article>,
+    current: usize,
+}
+
+/// Lists every part of a series, marking the one being read.
+#[component]
+fn SeriesBox(props: SeriesBoxProps) -> String {
+    let series_href = format!("/series/{}", props.series.slug);
+    let parts = props
+        .parts
+        .iter()
+        .enumerate()
+        .map(|(i, part)| {
+            if i == props.current {
+                html! {
+                    <li class="font-semibold" aria-current="page">{&part.title}</li>
+                }
+            } else {
+                let href = format!("/articles/{}", part.slug);
+                html! {
+                    <li><a href=href>{&part.title}</a></li>
+                }
+            }
+        })
+        .collect_fragment();
+
+    html! {
+        <nav class="series rounded-md border border-black bg-white px-4 py-3">
+            <p>
+                "Part " {props.current + 1} " of " {props.parts.len()} " in the series "
+                <a href=series_href>{props.series.name}</a>
+            </p>
+            <ol>{parts}</ol>
+        </nav>
+    }
+}
+
+#[props]
+pub struct SeriesNavProps {
+    parts: Vec<Article>,
+    current: usize,
+}
+
+/// Links to the previous and next parts of a series.
+#[component]
+fn SeriesNav(props: SeriesNavProps) -> String {
+    let link = |part: Option<&Article>, label: &str| {
+        part.map_or_else(String::new, |part| {
+            let href = format!("/articles/{}", part.slug);
+            html! {
+                <a href=href>
+                    <span>{label}</span>
+                    {&part.title}
+                </a>
+            }
+        })
+    };
+    let prev = props
+        .current
+        .checked_sub(1)
+        .and_then(|i| props.parts.get(i));
+    let next = props.parts.get(props.current + 1);
+
+    html! {
+        <nav class="series-nav">
+            <div>{link(prev, "Previous")}</div>
+            <div class="next">{link(next, "Next")}</div>
+        </nav>
+    }
+}
+
+#[props]
+pub struct BacklinksProps {
+    articles: Vec<Article>,
+}
+
+/// Lists the articles linking to the current one.
+#[component]
+fn Backlinks(props: BacklinksProps) -> String {
+    if props.articles.is_empty() {
+        return String::new();
+    }
+
+    let items = props
+        .articles
+        .into_iter()
+        .map(|article| {
+            let href = format!("/articles/{}", article.slug);
+            html! {
+                <li>
+                    <a href=href>{article.title}</a>
+                    <span class="text-gray-500">" · " {article.datetime.format("%B %d, %Y").to_string()}</span>
+                </li>
+            }
+        })
+        .collect_fragment();
+
+    html! {
+        <section>
+            <h2>"Referenced by"</h2>
+            <ul>{items}</ul>
+        </section>
+    }
+}
+
+#[props]
+pub struct RelatedArticlesProps {
+    articles: Vec<Article>,
+}
+
+#[component]
+fn RelatedArticles(props: RelatedArticlesProps) -> String {
+    if props.articles.is_empty() {
+        return String::new();
+    }
+
+    let items = props
+        .articles
+        .into_iter()
+        .map(|article| {
+            let href = format!("/articles/{}", article.slug);
+            html! {
+                <li>
+                    <a href=href>{article.title}</a>
+                    <span class="text-gray-500">" · " {article.reading_time} " min read"</span>
+                </li>
+            }
+        })
+        .collect_fragment();
+
+    html! {
+        <section>
+            <h2>"Related articles"</h2>
+            <ul>{items}</ul>
+        </section>
+    }
+}
+
+fn render_toc(entries: &[TocEntry]) -> String {
+    if entries.is_empty() {
+        return String::new();
+    }
+
+    let items = entries
+        .iter()
+        .map(|entry| {
+            let href = format!("#{}", entry.id);
+            html! {
+                <li>
+                    <a href=href>{&entry.title}</a>
+                    {render_toc(&entry.children)}
+                </li>
+            }
+        })
+        .collect_fragment();
+
+    html! {
+        <ul>{items}</ul>
+    }
+}
@@ -7,7 +7,11 @@ use rscx::{
 
 use crate::{
     bookmarks::{Bookmark, BookmarksRepo},
-    components::layout::{Header, Layout, SecondarySidebar, SidebarNavItem},
+    components::{
+        layout::{Header, Layout, SecondarySidebar, SidebarNavItem},
+        tags::TagList,
+    },
+    export::Export,
     icons::Link,
     meta::render_with_meta,
 };
@@ -16,12 +20,14 @@ use super::auth::AuthContext;
 
 pub async fn handler(
     uri: http::Uri,
+    export: Export,
     Extension(auth): Extension<AuthContext>,
     Extension(bookmarks_repo): Extension<BookmarksRepo>,
 ) -> impl IntoResponse {
     render_with_meta(
         || {
             provide_context(uri);
+            provide_context(export);
             provide_context(auth);
             provide_context(bookmarks_repo);
         },
@@ -38,6 +44,7 @@ pub async fn handler(
 
 pub async fn handler_bookmark(
     uri: http::Uri,
+    export: Export,
     Extension(auth): Extension<AuthContext>,
     Extension(bookmarks_repo): Extension<BookmarksRepo>,
     Path(slug): Path<String>,
@@ -47,6 +54,7 @@ pub async fn handler_bookmark(
     render_with_meta(
         || {
             provide_context(uri);
+            provide_context(export);
             provide_context(auth);
             provide_context(bookmarks_repo);
         },
@@ -169,6 +177,8 @@ fn BookmarkContent(props: BookmarkContentProps) -> String {
                             <br />
                             Bookmarked on {&props.bookmark.created_at.format("%B %e, %Y").to_string()}.
                         </p>
+
+                        <TagList tags=props.bookmark.tags />
                     </div>
 
                     <div class="typography">
@@ -0,0 +1,21 @@
+use axum::{http::header::CONTENT_TYPE, response::IntoResponse, Extension};
+
+use crate::{
+    apps::AppsRepo,
+    articles::ArticlesRepo,
+    bookmarks::BookmarksRepo,
+    feeds::{activity_feed, FeedFormat},
+};
+
+pub async fn handler(
+    Extension(articles_repo): Extension<ArticlesRepo>,
+    Extension(bookmarks_repo): Extension<BookmarksRepo>,
+    Extension(apps_repo): Extension<AppsRepo>,
+) -> impl IntoResponse {
+    let feed = activity_feed(
+        &articles_repo.list(),
+        &bookmarks_repo.list().await,
+        &apps_repo.list().await,
+    );
+    ([(CONTENT_TYPE, FeedFormat::Atom.content_type())], feed)
+}
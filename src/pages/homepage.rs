@@ -5,16 +5,18 @@ use rscx::{
 
 use crate::{
     components::layout::Layout,
+    export::Export,
     icons::{Github, LinkedIn, Twitter},
     images::StaticImg,
     meta::render_with_meta,
     AuthContext,
 };
 
-pub async fn handler(uri: http::Uri, auth: AuthContext) -> impl IntoResponse {
+pub async fn handler(uri: http::Uri, export: Export, auth: AuthContext) -> impl IntoResponse {
     render_with_meta(
         || {
             provide_context(uri);
+            provide_context(export);
             provide_context(auth);
         },
         || async {
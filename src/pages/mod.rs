@@ -3,4 +3,9 @@ pub mod apps;
 pub mod articles;
 pub mod auth;
 pub mod bookmarks;
+pub mod feed;
 pub mod homepage;
+pub mod search;
+pub mod series;
+pub mod sitemap;
+pub mod tags;
@@ -0,0 +1,60 @@
+use axum::{extract::Query, http, response::IntoResponse, Extension};
+use rscx::{context::provide_context, html};
+
+use crate::{
+    components::{
+        layout::{Header, Layout},
+        search::{SearchQuery, SearchResults},
+    },
+    meta::render_with_meta,
+    search::SearchIndex,
+};
+
+use super::auth::AuthContext;
+
+pub async fn handler(
+    uri: http::Uri,
+    Extension(auth): Extension<AuthContext>,
+    Extension(search_index): Extension<SearchIndex>,
+    Query(SearchQuery { q }): Query<SearchQuery>,
+) -> impl IntoResponse {
+    render_with_meta(
+        || {
+            provide_context(uri);
+            provide_context(auth);
+        },
+        || async move {
+            let results = if q.trim().is_empty() {
+                String::new()
+            } else {
+                html! {
+                    <div class="divide-y-2 divide-black rounded-md border-2 border-black bg-white">
+                        <SearchResults index=search_index query=q.clone() limit=50 />
+                    </div>
+                }
+            };
+
+            html! {
+                <Layout title="Search - Antonio Pitasi">
+                    <main class="relative min-h-full w-full bg-floralwhite pb-24 lg:pb-0">
+                        <Header title="Search".into() />
+                        <div class="mx-auto max-w-2xl space-y-6 p-8">
+                            <h1 class="title font-neu font-semibold text-darkviolet text-4xl">Search</h1>
+                            <form action="/search" method="get">
+                                <input
+                                    type="search"
+                                    name="q"
+                                    value=q
+                                    placeholder="Search articles, bookmarks and apps..."
+                                    class="w-full rounded-md border-2 border-black bg-white px-3 py-2 shadow-neu-1 focus:outline-none"
+                                />
+                            </form>
+                            {results}
+                        </div>
+                    </main>
+                </Layout>
+            }
+        },
+    )
+    .await
+}
@@ -0,0 +1,100 @@
+use axum::{
+    extract::Path,
+    http,
+    response::{IntoResponse, Response},
+    Extension,
+};
+use rscx::{component, context::provide_context, html, props, CollectFragment};
+
+use crate::{
+    articles::{Article, ArticlesRepo},
+    components::layout::{Header, Layout},
+    export::Export,
+    meta::render_with_meta,
+    pages::articles::Articles,
+};
+
+use super::auth::AuthContext;
+
+pub async fn handler(
+    uri: http::Uri,
+    export: Export,
+    Extension(auth): Extension<AuthContext>,
+    Extension(articles_repo): Extension<ArticlesRepo>,
+    Path(slug): Path<String>,
+) -> Response {
+    let parts = articles_repo.list_series(&slug);
+    let Some(name) = parts
+        .first()
+        .and_then(|a| a.series.as_ref())
+        .map(|s| s.name.clone())
+    else {
+        return (http::StatusCode::NOT_FOUND, "404 not found").into_response();
+    };
+
+    let title = format!("{name} - Antonio Pitasi");
+    render_with_meta(
+        || {
+            provide_context(uri);
+            provide_context(export);
+            provide_context(auth);
+            provide_context(articles_repo);
+        },
+        || async move {
+            let description =
+                format!("All the parts of \"{name}\", a series of articles by Antonio.");
+            html! {
+                <Layout title=title description=description>
+                    <Articles>
+                        <SeriesContent name=name parts=parts />
+                    </Articles>
+                </Layout>
+            }
+        },
+    )
+    .await
+    .into_response()
+}
+
+#[props]
+pub struct SeriesContentProps {
+    name: String,
+    parts: Vec<Article>,
+}
+
+#[component]
+fn SeriesContent(props: SeriesContentProps) -> String {
+    let parts = props
+        .parts
+        .into_iter()
+        .map(|part| {
+            let href = format!("/articles/{}", part.slug);
+            html! {
+                <li>
+                    <a href=href>{part.title}</a>
+                    <span class="text-gray-500">
+                        " · " {part.datetime.format("%B %d, %Y").to_string()}
+                        " · " {part.reading_time} " min read"
+                    </span>
+                </li>
+            }
+        })
+        .collect_fragment();
+
+    html! {
+        <main class="typography relative min-h-full bg-floralwhite pb-24 lg:pb-0">
+            <Header title=props.name.clone() />
+            <article class="w-full bg-floralwhite p-8">
+                <div class="mx-auto max-w-2xl">
+                    <h1 class="title font-neu font-semibold text-darkviolet text-4xl">
+                        {props.name}
+                    </h1>
+                    <p>"A series of articles, meant to be read in this order."</p>
+                    <div class="series">
+                        <ol>{parts}</ol>
+                    </div>
+                </div>
+            </article>
+        </main>
+    }
+}
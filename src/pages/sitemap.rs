@@ -0,0 +1,88 @@
+use std::{collections::BTreeSet, fmt::Write};
+
+use axum::{http::header::CONTENT_TYPE, response::IntoResponse, Extension};
+use chrono::{DateTime, FixedOffset};
+use rscx::html_escape::encode_text;
+
+use crate::{
+    apps::AppsRepo,
+    articles::ArticlesRepo,
+    bookmarks::BookmarksRepo,
+    tags::{count_tags, tag_path},
+    BASE_URL,
+};
+
+pub async fn sitemap(
+    Extension(articles_repo): Extension<ArticlesRepo>,
+    Extension(bookmarks_repo): Extension<BookmarksRepo>,
+    Extension(apps_repo): Extension<AppsRepo>,
+) -> impl IntoResponse {
+    let articles = articles_repo.list();
+    let bookmarks = bookmarks_repo.list().await;
+    let apps = apps_repo.list().await;
+
+    let latest_article = articles.first().map(|a| a.datetime);
+    let latest_bookmark = bookmarks.iter().map(|b| b.created_at.fixed_offset()).max();
+
+    // (path, lastmod)
+    let mut urls: Vec<(String, Option<DateTime<FixedOffset>>)> = vec![
+        ("/".to_string(), latest_article.max(latest_bookmark)),
+        ("/articles".to_string(), latest_article),
+        ("/bookmarks".to_string(), latest_bookmark),
+        ("/uses".to_string(), None),
+        ("/tags".to_string(), None),
+    ];
+    urls.extend(
+        articles
+            .iter()
+            .map(|a| (format!("/articles/{}", a.slug), Some(a.datetime))),
+    );
+    urls.extend(
+        articles
+            .iter()
+            .filter_map(|a| a.series.as_ref())
+            .map(|s| format!("/series/{}", s.slug))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .map(|path| (path, None)),
+    );
+    urls.extend(bookmarks.iter().map(|b| {
+        (
+            format!("/bookmarks/{}", b.slug),
+            Some(b.created_at.fixed_offset()),
+        )
+    }));
+    urls.extend(apps.iter().map(|a| (format!("/uses/{}", a.slug), None)));
+    urls.extend(
+        count_tags(&articles, &bookmarks, &apps)
+            .into_keys()
+            .map(|tag| (tag_path(&tag), None)),
+    );
+
+    let mut xml = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+"#,
+    );
+    for (path, lastmod) in urls {
+        let _ = write!(xml, "<url><loc>{}{}</loc>", BASE_URL, encode_text(&path));
+        if let Some(lastmod) = lastmod {
+            let _ = write!(xml, "<lastmod>{}</lastmod>", lastmod.to_rfc3339());
+        }
+        xml.push_str("</url>\n");
+    }
+    xml.push_str("</urlset>\n");
+
+    ([(CONTENT_TYPE, "application/xml")], xml)
+}
+
+pub async fn robots() -> impl IntoResponse {
+    format!(
+        "User-agent: *
+Disallow: /admin
+Disallow: /components
+
+Sitemap: {BASE_URL}/sitemap.xml
+"
+    )
+}
@@ -0,0 +1,241 @@
+use axum::{
+    extract::Path,
+    http,
+    response::{IntoResponse, Response},
+    Extension,
+};
+use rscx::{
+    component,
+    context::{expect_context, provide_context},
+    html,
+    html_escape::encode_text,
+    props, CollectFragment, CollectFragmentAsync,
+};
+
+use crate::{
+    apps::AppsRepo,
+    articles::ArticlesRepo,
+    bookmarks::BookmarksRepo,
+    components::layout::{Header, Layout, SecondarySidebar, SidebarNavItem},
+    conditional::{conditional, etag},
+    export::Export,
+    feeds::{tag_feed, FeedFormat},
+    meta::{render_with_meta, Dedup},
+    tags::{count_tags, normalize_tag, tag_path},
+};
+
+use super::auth::AuthContext;
+
+pub async fn handler(
+    uri: http::Uri,
+    export: Export,
+    Extension(auth): Extension<AuthContext>,
+    Extension(articles_repo): Extension<ArticlesRepo>,
+    Extension(bookmarks_repo): Extension<BookmarksRepo>,
+    Extension(apps_repo): Extension<AppsRepo>,
+) -> impl IntoResponse {
+    render_with_meta(
+        || {
+            provide_context(uri);
+            provide_context(export);
+            provide_context(auth);
+            provide_context(articles_repo);
+            provide_context(bookmarks_repo);
+            provide_context(apps_repo);
+        },
+        || async {
+            html! {
+                <Layout title="Tags - Antonio Pitasi" description="Antonio's articles, bookmarks and apps, by topic.">
+                    <Tags />
+                </Layout>
+            }
+        },
+    )
+    .await
+}
+
+pub async fn handler_tag(
+    uri: http::Uri,
+    export: Export,
+    Extension(auth): Extension<AuthContext>,
+    Extension(articles_repo): Extension<ArticlesRepo>,
+    Extension(bookmarks_repo): Extension<BookmarksRepo>,
+    Extension(apps_repo): Extension<AppsRepo>,
+    Path(tag): Path<String>,
+) -> Response {
+    let tag = normalize_tag(&tag);
+    let articles = articles_repo.list_by_tag(&tag);
+    let bookmarks = bookmarks_repo.list_by_tag(&tag).await;
+    let apps = apps_repo.list_by_tag(&tag).await;
+    if articles.is_empty() && bookmarks.is_empty() && apps.is_empty() {
+        return (http::StatusCode::NOT_FOUND, "404 not found").into_response();
+    }
+
+    let articles = articles
+        .into_iter()
+        .map(|a| (format!("/articles/{}", a.slug), a.title))
+        .collect();
+    let bookmarks = bookmarks
+        .into_iter()
+        .map(|b| (format!("/bookmarks/{}", b.slug), b.title))
+        .collect();
+    let apps = apps
+        .into_iter()
+        .map(|a| (format!("/uses/{}", a.slug), a.name))
+        .collect();
+
+    let title = format!("#{} - Tags - Antonio Pitasi", encode_text(&tag));
+    render_with_meta(
+        || {
+            provide_context(uri);
+            provide_context(export);
+            provide_context(auth);
+            provide_context(articles_repo);
+            provide_context(bookmarks_repo);
+            provide_context(apps_repo);
+        },
+        || async move {
+            html! {
+                <Layout title=title>
+                    <Tags>
+                        <TagContent tag=tag articles=articles bookmarks=bookmarks apps=apps />
+                    </Tags>
+                </Layout>
+            }
+        },
+    )
+    .await
+    .into_response()
+}
+
+pub async fn tag_rss(
+    headers: http::HeaderMap,
+    Extension(articles_repo): Extension<ArticlesRepo>,
+    Extension(bookmarks_repo): Extension<BookmarksRepo>,
+    Extension(apps_repo): Extension<AppsRepo>,
+    Path(tag): Path<String>,
+) -> Response {
+    let tag = normalize_tag(&tag);
+    let articles = articles_repo.list_by_tag(&tag);
+    let bookmarks = bookmarks_repo.list_by_tag(&tag).await;
+    let apps = apps_repo.list_by_tag(&tag).await;
+    if articles.is_empty() && bookmarks.is_empty() && apps.is_empty() {
+        return (http::StatusCode::NOT_FOUND, "404 not found").into_response();
+    }
+
+    let feed = tag_feed(&tag, &tag_path(&tag), &articles, &bookmarks, &apps);
+    conditional(
+        &headers,
+        &etag(feed.as_bytes()),
+        None,
+        (
+            [(http::header::CONTENT_TYPE, FeedFormat::Atom.content_type())],
+            feed,
+        ),
+    )
+}
+
+#[props]
+pub struct TagsProps {
+    #[builder(default)]
+    children: String,
+}
+
+#[component]
+pub async fn Tags(props: TagsProps) -> String {
+    let articles = expect_context::<ArticlesRepo>().list();
+    let bookmarks = expect_context::<BookmarksRepo>().list().await;
+    let apps = expect_context::<AppsRepo>().list().await;
+
+    let v = count_tags(&articles, &bookmarks, &apps)
+        .into_iter()
+        .map(|(tag, count)| async move {
+            let href = tag_path(&tag);
+            html! {
+                <SidebarNavItem href=href>
+                    <div class="flex flex-row w-full justify-between">
+                        <span class="font-semibold">"#"{encode_text(&tag)}</span>
+                        <span class="opacity-60">{count}</span>
+                    </div>
+                </SidebarNavItem>
+            }
+        })
+        .collect_fragment_async()
+        .await;
+
+    html! {
+        <div class="relative h-full w-full flex-row lg:grid lg:grid-cols-[20rem_minmax(0,1fr)]">
+            <SecondarySidebar title="Tags".into()>
+                {v}
+            </SecondarySidebar>
+            { if props.children.is_empty() { html!{} } else {
+                html! {
+                    <div class="absolute inset-0 lg:static empty:hidden">
+                        {props.children}
+                    </div>
+                }
+            } }
+        </div>
+    }
+}
+
+#[props]
+pub struct TagContentProps {
+    tag: String,
+    /// (href, title) of each item, grouped by content type.
+    articles: Vec<(String, String)>,
+    bookmarks: Vec<(String, String)>,
+    apps: Vec<(String, String)>,
+}
+
+#[component]
+fn TagContent(props: TagContentProps) -> String {
+    let title = format!("#{}", encode_text(&props.tag));
+    let feed_url = format!("{}/atom.xml", tag_path(&props.tag));
+    let feed_title = format!("RSS Feed for anto.pt posts about #{}", props.tag);
+
+    let sections = [
+        ("Articles", props.articles),
+        ("Bookmarks", props.bookmarks),
+        ("Uses", props.apps),
+    ]
+    .into_iter()
+    .filter(|(_, items)| !items.is_empty())
+    .map(|(name, items)| {
+        let items = items
+            .into_iter()
+            .map(|(href, title)| {
+                html! {
+                    <li><a href=href>{title}</a></li>
+                }
+            })
+            .collect_fragment();
+        html! {
+            <section>
+                <h2>{name}</h2>
+                <ul>{items}</ul>
+            </section>
+        }
+    })
+    .collect_fragment();
+
+    html! {
+        <Dedup id="atom".to_string()>
+            <link rel="alternate" type="application/atom+xml" title=feed_title href=&feed_url />
+        </Dedup>
+        <main class="typography relative min-h-full bg-floralwhite pb-24 lg:pb-0">
+            <Header title=title.clone() />
+            <article class="w-full bg-floralwhite p-8">
+                <div class="mx-auto max-w-2xl">
+                    <h1 class="title font-neu font-semibold text-darkviolet text-4xl">
+                        {title}
+                    </h1>
+                    <p>
+                        <a href=feed_url>"Subscribe to new posts with this tag"</a>
+                    </p>
+                    {sections}
+                </div>
+            </article>
+        </main>
+    }
+}
@@ -0,0 +1,362 @@
+use reqwest::Url;
+use rscx::{
+    html,
+    html_escape::{encode_double_quoted_attribute, encode_text},
+};
+use rscx_mdx::mdx::{Mdx, MdxComponentProps};
+use scraper::{ElementRef, Html, Node};
+
+use crate::{
+    articles::Article,
+    mdx::{ComponentRegistry, Text},
+    BASE_URL,
+};
+
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "audio",
+    "blockquote",
+    "div",
+    "figcaption",
+    "figure",
+    "footer",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "iframe",
+    "main",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "ul",
+    "video",
+];
+
+/// Elements dropped altogether, with their content.
+const SKIPPED_ELEMENTS: &[&str] = &["button", "form", "noscript", "script", "style", "template"];
+
+/// Renders an article as plain text: its title and date, then its body with
+/// components flattened to text, as `components` describe them, and links
+/// followed by their URL.
+///
+/// # Panics
+///
+/// Panics if the slug of the article doesn't make a valid URL.
+pub async fn render(article: &Article, components: &ComponentRegistry) -> String {
+    let components = components.clone();
+    let handler = move |name: String, props: MdxComponentProps| {
+        let text = components
+            .text(&name, props.attributes)
+            .unwrap_or(Text::Children);
+        async move { component_html(text, props.children) }
+    };
+    let html = html! {
+        <Mdx handler=handler source=article.body().to_string() />
+    };
+    let base = Url::parse(&format!("{BASE_URL}/articles/{}", article.slug)).unwrap();
+    let fragment = Html::parse_fragment(&html);
+
+    let mut res = vec![
+        underline(&article.title, '='),
+        format!(
+            "{} · {} min read",
+            article.datetime.format("%B %d, %Y"),
+            article.reading_time
+        ),
+    ];
+    res.extend(blocks(fragment.root_element(), &base));
+    res.join("\n\n") + "\n"
+}
+
+/// Renders a component as the plain HTML its text can be taken from.
+fn component_html(text: Text, children: String) -> String {
+    match text {
+        Text::Children => children,
+        Text::Labelled(label) => format!("<div><p>{}: {children}</p></div>", encode_text(&label)),
+        Text::Titled(title) => format!("<div><p>{}</p>{children}</div>", encode_text(&title)),
+        Text::Link { url, label } => format!(
+            r#"<p><a href="{}">{}</a></p>"#,
+            encode_double_quoted_attribute(&url),
+            encode_text(&label)
+        ),
+        Text::Image { alt, .. } => {
+            format!(r#"<img alt="{}">"#, encode_double_quoted_attribute(&alt))
+        }
+    }
+}
+
+/// Converts the children of `el` to a list of text blocks. Inline content
+/// between blocks becomes a paragraph.
+fn blocks(el: ElementRef, base: &Url) -> Vec<String> {
+    let mut res = vec![];
+    let mut paragraph = String::new();
+    for node in el.children() {
+        if let Some(child) = ElementRef::wrap(node) {
+            if BLOCK_ELEMENTS.contains(&child.value().name()) {
+                push_paragraph(&mut res, &mut paragraph);
+                res.extend(block(child, base));
+            } else {
+                paragraph.push_str(&inline(child, base));
+            }
+        } else if let Node::Text(text) = node.value() {
+            paragraph.push_str(&collapse_whitespace(text));
+        }
+    }
+    push_paragraph(&mut res, &mut paragraph);
+    res
+}
+
+fn push_paragraph(blocks: &mut Vec<String>, paragraph: &mut String) {
+    let text = tidy_paragraph(paragraph);
+    if !text.is_empty() {
+        blocks.push(text);
+    }
+    paragraph.clear();
+}
+
+/// Trims the lines of a paragraph, which has more than one only after line
+/// breaks.
+fn tidy_paragraph(text: &str) -> String {
+    text.lines()
+        .map(str::trim)
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+fn block(el: ElementRef, base: &Url) -> Vec<String> {
+    let name = el.value().name();
+    let text = match name {
+        "p" | "figcaption" | "h3" | "h4" | "h5" | "h6" => {
+            tidy_paragraph(&inline_children(el, base))
+        }
+        "h1" => underline(&tidy_paragraph(&inline_children(el, base)), '='),
+        "h2" => underline(&tidy_paragraph(&inline_children(el, base)), '-'),
+        "hr" => "* * *".to_string(),
+        "ul" => list(el, None, base),
+        "ol" => {
+            let start = el
+                .value()
+                .attr("start")
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(1);
+            list(el, Some(start), base)
+        }
+        "blockquote" => indent(&blocks(el, base).join("\n\n"), "> "),
+        "pre" => indent(el.text().collect::<String>().trim_end_matches('\n'), "    "),
+        "table" => table(el, base),
+        "audio" | "iframe" | "video" => el
+            .value()
+            .attr("src")
+            .and_then(|src| base.join(src).ok())
+            .map(|src| src.to_string())
+            .unwrap_or_default(),
+        _ => return blocks(el, base),
+    };
+
+    if text.trim().is_empty() {
+        vec![]
+    } else {
+        vec![text]
+    }
+}
+
+fn underline(text: &str, c: char) -> String {
+    let width = text
+        .lines()
+        .map(|l| l.chars().count())
+        .max()
+        .unwrap_or_default();
+    format!("{text}\n{}", c.to_string().repeat(width))
+}
+
+/// Prefixes every line of `text`, but blank ones are only given the prefix
+/// without its trailing spaces.
+fn indent(text: &str, prefix: &str) -> String {
+    text.lines()
+        .map(|line| {
+            if line.is_empty() {
+                prefix.trim_end().to_string()
+            } else {
+                format!("{prefix}{line}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn list(el: ElementRef, start: Option<usize>, base: &Url) -> String {
+    el.children()
+        .filter_map(ElementRef::wrap)
+        .filter(|item| item.value().name() == "li")
+        .enumerate()
+        .map(|(i, item)| {
+            let marker = start.map_or_else(|| "-".to_string(), |start| format!("{}.", start + i));
+            let content = blocks(item, base).join("\n");
+            let content = indent(&content, &" ".repeat(marker.len() + 1));
+            format!("{marker} {}", content.trim_start())
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn table(el: ElementRef, base: &Url) -> String {
+    el.descendants()
+        .filter_map(ElementRef::wrap)
+        .filter(|row| row.value().name() == "tr")
+        .map(|row| {
+            row.children()
+                .filter_map(ElementRef::wrap)
+                .filter(|cell| matches!(cell.value().name(), "td" | "th"))
+                .map(|cell| {
+                    inline_children(cell, base)
+                        .split_whitespace()
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                })
+                .collect::<Vec<_>>()
+                .join(" | ")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn inline_children(el: ElementRef, base: &Url) -> String {
+    el.children()
+        .map(|node| {
+            if let Some(child) = ElementRef::wrap(node) {
+                inline(child, base)
+            } else if let Node::Text(text) = node.value() {
+                collapse_whitespace(text)
+            } else {
+                String::new()
+            }
+        })
+        .collect()
+}
+
+fn inline(el: ElementRef, base: &Url) -> String {
+    match el.value().name() {
+        "br" => "\n".to_string(),
+        "img" => el.value().attr("alt").unwrap_or_default().to_string(),
+        "a" => {
+            let text = inline_children(el, base);
+            let href = el
+                .value()
+                .attr("href")
+                .filter(|href| !href.starts_with('#'))
+                .and_then(|href| base.join(href).ok());
+            match href {
+                Some(href) if text.trim() != href.as_str() && !text.trim().is_empty() => {
+                    format!("{text} ({href})")
+                }
+                Some(href) if text.trim().is_empty() => href.to_string(),
+                _ => text,
+            }
+        }
+        name if SKIPPED_ELEMENTS.contains(&name) => String::new(),
+        _ => inline_children(el, base),
+    }
+}
+
+/// Collapses runs of whitespace to a single space. Leading and trailing
+/// whitespace is kept, collapsed too, so inline text can be concatenated.
+#[must_use]
+pub fn collapse_whitespace(text: &str) -> String {
+    let mut res = String::with_capacity(text.len());
+    let mut last_was_space = false;
+    for c in text.chars() {
+        if c.is_whitespace() {
+            if !last_was_space {
+                res.push(' ');
+            }
+            last_was_space = true;
+        } else {
+            res.push(c);
+            last_was_space = false;
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(html: &str) -> String {
+        let base = Url::parse("https://anto.pt/articles/a").unwrap();
+        blocks(Html::parse_fragment(html).root_element(), &base).join("\n\n")
+    }
+
+    #[test]
+    fn renders_blocks() {
+        assert_eq!(
+            text("<h2>Title</h2><p>Some\n  <em>text</em></p><hr><pre>fn main() {}\n</pre>"),
+            "Title\n-----\n\nSome text\n\n* * *\n\n    fn main() {}"
+        );
+    }
+
+    #[test]
+    fn renders_lists_and_quotes() {
+        assert_eq!(
+            text(r#"<ol start="3"><li>a</li><li>b<ul><li>c</li></ul></li></ol>"#),
+            "3. a\n4. b\n   - c"
+        );
+        assert_eq!(
+            text("<blockquote><p>a</p><p>b</p></blockquote>"),
+            "> a\n>\n> b"
+        );
+    }
+
+    #[test]
+    fn follows_links_with_their_url() {
+        assert_eq!(
+            text(
+                r##"<p><a href="/b">B</a>, <a href="#top">top</a>, <a href="https://x.y/"></a></p>"##
+            ),
+            "B (https://anto.pt/b), top, https://x.y/"
+        );
+    }
+
+    #[test]
+    fn skips_scripts_and_forms() {
+        assert_eq!(text("<p>a<script>b</script><button>c</button></p>"), "a");
+    }
+
+    #[test]
+    fn renders_components_as_text() {
+        let html = [
+            component_html(Text::Labelled("bulb".into()), "Hi".into()),
+            component_html(Text::Titled("Note:".into()), "<p>Careful</p>".into()),
+            component_html(
+                Text::Link {
+                    url: "/uses/x".into(),
+                    label: String::new(),
+                },
+                String::new(),
+            ),
+            component_html(
+                Text::Image {
+                    src: "a.png".into(),
+                    alt: "A <b>".into(),
+                },
+                String::new(),
+            ),
+        ]
+        .concat();
+        assert_eq!(
+            text(&html),
+            "bulb: Hi\n\nNote:\n\nCareful\n\nhttps://anto.pt/uses/x\n\nA <b>"
+        );
+    }
+}
@@ -0,0 +1,154 @@
+use axum::{
+    http::{header::LOCATION, Method, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Extension,
+};
+use sqlx::{postgres::PgQueryResult, Executor, FromRow, PgPool};
+
+use crate::articles::ArticlesRepo;
+
+#[derive(Debug, FromRow)]
+pub struct Redirect {
+    pub from_path: String,
+    pub to_path: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Clone)]
+pub struct RedirectsRepo {
+    pool: PgPool,
+}
+
+impl RedirectsRepo {
+    #[must_use]
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Lists the redirects, by path.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the database can't be queried.
+    pub async fn list(&self) -> Vec<Redirect> {
+        let mut conn = self.pool.acquire().await.unwrap();
+        sqlx::query_as::<_, Redirect>(
+            r"
+            select * from redirects
+            order by from_path
+        ",
+        )
+        .fetch_all(&mut conn)
+        .await
+        .unwrap()
+    }
+
+    /// Returns the redirect registered for `path`, which must be
+    /// normalized with [`normalize_path`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the database can't be queried.
+    pub async fn get(&self, path: &str) -> Option<Redirect> {
+        let mut conn = self.pool.acquire().await.unwrap();
+        sqlx::query_as::<_, Redirect>(
+            r"
+            select * from redirects
+            where from_path = $1
+        ",
+        )
+        .bind(path)
+        .fetch_optional(&mut conn)
+        .await
+        .unwrap()
+    }
+
+    /// Adds a redirect, replacing the one already registered for the same
+    /// path.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the insert fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no connection to the database can be acquired.
+    pub async fn add(&self, from_path: &str, to_path: &str) -> Result<PgQueryResult, sqlx::Error> {
+        let mut conn = self.pool.acquire().await.unwrap();
+        conn.execute(
+            sqlx::query(
+                r"
+            insert into redirects (from_path, to_path)
+            values ($1, $2)
+            on conflict (from_path) do update set to_path = excluded.to_path
+            ",
+            )
+            .bind(normalize_path(from_path))
+            .bind(to_path),
+        )
+        .await
+    }
+
+    /// Removes the redirect registered for `from_path`, if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the delete fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no connection to the database can be acquired.
+    pub async fn delete(&self, from_path: &str) -> Result<PgQueryResult, sqlx::Error> {
+        let mut conn = self.pool.acquire().await.unwrap();
+        conn.execute(
+            sqlx::query(
+                r"
+            delete from redirects
+            where from_path = $1
+            ",
+            )
+            .bind(from_path),
+        )
+        .await
+    }
+}
+
+/// Normalizes a path so that `/foo`, `/foo/` and `foo` are looked up the
+/// same way. Query strings and fragments are dropped.
+#[must_use]
+pub fn normalize_path(path: &str) -> String {
+    let path = path
+        .split(['?', '#'])
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .trim_matches('/');
+    format!("/{path}")
+}
+
+/// Middleware answering with `301 Moved Permanently` instead of `404 Not
+/// Found` when the path is an alias of an article or has a redirect in the
+/// database. Aliases win over the database.
+pub async fn redirect_not_found<B>(
+    Extension(articles_repo): Extension<ArticlesRepo>,
+    Extension(redirects_repo): Extension<RedirectsRepo>,
+    req: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let method = req.method().clone();
+    let path = normalize_path(req.uri().path());
+    let res = next.run(req).await;
+    if res.status() != StatusCode::NOT_FOUND || !matches!(method, Method::GET | Method::HEAD) {
+        return res;
+    }
+
+    let location = if let Some(article) = articles_repo.get_article_by_alias(&path) {
+        format!("/articles/{}", article.slug)
+    } else if let Some(redirect) = redirects_repo.get(&path).await {
+        redirect.to_path
+    } else {
+        return res;
+    };
+    (StatusCode::MOVED_PERMANENTLY, [(LOCATION, location)]).into_response()
+}
@@ -0,0 +1,308 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    ops::Range,
+    sync::{Arc, RwLock},
+};
+
+use chrono::{DateTime, FixedOffset, Utc};
+use rscx::html_escape::encode_text;
+use scraper::Html;
+
+use crate::{apps::App, articles::Article, bookmarks::Bookmark};
+
+/// Title matches weigh more than body matches.
+const TITLE_WEIGHT: f64 = 3.0;
+/// Number of words shown in a result snippet.
+const SNIPPET_WORDS: usize = 30;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Kind {
+    Article,
+    Bookmark,
+    App,
+}
+
+impl Kind {
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            Kind::Article => "Article",
+            Kind::Bookmark => "Bookmark",
+            Kind::App => "Uses",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Document {
+    pub kind: Kind,
+    pub url: String,
+    pub title: String,
+    pub body: String,
+    /// Documents are hidden from results until this moment, if set.
+    pub visible_from: Option<DateTime<FixedOffset>>,
+}
+
+impl Document {
+    fn is_visible(&self) -> bool {
+        self.visible_from.map_or(true, |t| t <= Utc::now())
+    }
+}
+
+impl From<&Article> for Document {
+    fn from(article: &Article) -> Self {
+        Self {
+            kind: Kind::Article,
+            url: format!("/articles/{}", article.slug),
+            title: article.title.clone(),
+            body: html_to_text(&article.content),
+            visible_from: Some(article.publish_at),
+        }
+    }
+}
+
+impl From<&Bookmark> for Document {
+    fn from(bookmark: &Bookmark) -> Self {
+        Self {
+            kind: Kind::Bookmark,
+            url: format!("/bookmarks/{}", bookmark.slug),
+            title: bookmark.title.clone(),
+            body: format!("{} {}", bookmark.hostname, bookmark.description),
+            visible_from: None,
+        }
+    }
+}
+
+impl From<&App> for Document {
+    fn from(app: &App) -> Self {
+        Self {
+            kind: Kind::App,
+            url: format!("/uses/{}", app.slug),
+            title: app.name.clone(),
+            body: html_to_text(&app.description),
+            visible_from: None,
+        }
+    }
+}
+
+pub struct SearchResult {
+    pub document: Document,
+    pub score: f64,
+    /// HTML-escaped title, with the matching words wrapped in `<mark>`.
+    pub title: String,
+    /// HTML-escaped excerpt of the body, with the matching words wrapped in
+    /// `<mark>`.
+    pub snippet: String,
+}
+
+#[derive(Default)]
+struct Index {
+    sources: HashMap<Kind, Vec<Document>>,
+    documents: Vec<Document>,
+    /// Term -> (document, weight), sorted so that terms can be looked up by
+    /// prefix.
+    postings: BTreeMap<String, Vec<(usize, f64)>>,
+    /// Total weight of the terms of each document, so that long documents
+    /// don't win just by repeating words more often.
+    lengths: Vec<f64>,
+}
+
+impl Index {
+    fn rebuild(&mut self) {
+        self.documents = self.sources.values().flatten().cloned().collect();
+        self.postings.clear();
+        self.lengths.clear();
+
+        for (i, doc) in self.documents.iter().enumerate() {
+            let mut weights: HashMap<String, f64> = HashMap::new();
+            for (_, term) in tokenize(&doc.title) {
+                *weights.entry(term).or_default() += TITLE_WEIGHT;
+            }
+            for (_, term) in tokenize(&doc.body) {
+                *weights.entry(term).or_default() += 1.0;
+            }
+
+            self.lengths.push(weights.values().sum::<f64>().max(1.0));
+            for (term, weight) in weights {
+                self.postings.entry(term).or_default().push((i, weight));
+            }
+        }
+    }
+}
+
+/// In-memory inverted index of articles, bookmarks and apps.
+///
+/// Each kind of content is replaced as a whole via the `index_*` methods, so
+/// the index can be refreshed whenever one of the repos changes.
+#[derive(Clone, Default)]
+pub struct SearchIndex {
+    index: Arc<RwLock<Index>>,
+}
+
+impl SearchIndex {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn index_articles(&self, articles: &[Article]) {
+        self.replace(Kind::Article, articles.iter().map(From::from).collect());
+    }
+
+    pub fn index_bookmarks(&self, bookmarks: &[Bookmark]) {
+        self.replace(Kind::Bookmark, bookmarks.iter().map(From::from).collect());
+    }
+
+    pub fn index_apps(&self, apps: &[App]) {
+        self.replace(Kind::App, apps.iter().map(From::from).collect());
+    }
+
+    fn replace(&self, kind: Kind, documents: Vec<Document>) {
+        let mut index = self.index.write().unwrap();
+        index.sources.insert(kind, documents);
+        index.rebuild();
+    }
+
+    /// Returns the documents matching every word of `query`, best first.
+    /// The last word also matches as a prefix, as it may be incomplete while
+    /// the user is typing.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a thread panicked while holding the lock on the index.
+    #[must_use]
+    // document counts are far from the 52 bits of an `f64` mantissa
+    #[allow(clippy::cast_precision_loss)]
+    pub fn search(&self, query: &str, limit: usize) -> Vec<SearchResult> {
+        let terms = tokenize(query).map(|(_, t)| t).collect::<Vec<_>>();
+        if terms.is_empty() {
+            return vec![];
+        }
+
+        let index = self.index.read().unwrap();
+        let n = index.documents.len() as f64;
+        let mut scores: HashMap<usize, f64> = HashMap::new();
+        let mut matched: HashMap<usize, usize> = HashMap::new();
+
+        for (i, term) in terms.iter().enumerate() {
+            // the last term may still be being typed: it matches every term
+            // starting with it
+            let matching = if i == terms.len() - 1 {
+                index
+                    .postings
+                    .range::<String, _>(term..)
+                    .take_while(|(indexed, _)| indexed.starts_with(term.as_str()))
+                    .map(|(_, postings)| postings)
+                    .collect::<Vec<_>>()
+            } else {
+                index.postings.get(term).into_iter().collect()
+            };
+
+            let mut term_scores: HashMap<usize, f64> = HashMap::new();
+            for postings in matching {
+                let idf = (1.0 + n / postings.len() as f64).ln();
+                for (doc, weight) in postings {
+                    *term_scores.entry(*doc).or_default() += weight * idf;
+                }
+            }
+            for (doc, score) in term_scores {
+                *scores.entry(doc).or_default() += score;
+                *matched.entry(doc).or_default() += 1;
+            }
+        }
+
+        let mut results = scores
+            .into_iter()
+            .filter(|(doc, _)| matched[doc] == terms.len())
+            .filter(|(doc, _)| index.documents[*doc].is_visible())
+            .map(|(doc, score)| (doc, score / (1.0 + index.lengths[doc].ln())))
+            .collect::<Vec<_>>();
+        results.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        results
+            .into_iter()
+            .take(limit)
+            .map(|(doc, score)| {
+                let document = index.documents[doc].clone();
+                SearchResult {
+                    title: highlight(&document.title, &terms, usize::MAX),
+                    snippet: highlight(&document.body, &terms, SNIPPET_WORDS),
+                    document,
+                    score,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Splits `text` into lowercase words, along with their position in `text`.
+pub fn tokenize(text: &str) -> impl Iterator<Item = (Range<usize>, String)> + '_ {
+    let mut start = None;
+    text.char_indices()
+        .chain(std::iter::once((text.len(), ' ')))
+        .filter_map(move |(i, c)| match (c.is_alphanumeric(), start) {
+            (true, None) => {
+                start = Some(i);
+                None
+            }
+            (false, Some(s)) => {
+                start = None;
+                Some((s..i, text[s..i].to_lowercase()))
+            }
+            _ => None,
+        })
+}
+
+/// Escapes `text` and wraps the words matching `terms` in `<mark>`. At most
+/// `max_words` words are kept, centered around the first match.
+fn highlight(text: &str, terms: &[String], max_words: usize) -> String {
+    let words = tokenize(text).collect::<Vec<_>>();
+    let is_match = |word: &str| terms.iter().any(|t| word.starts_with(t.as_str()));
+
+    let first_match = words.iter().position(|(_, w)| is_match(w)).unwrap_or(0);
+    let from = first_match.saturating_sub(max_words / 4);
+    let to = from.saturating_add(max_words).min(words.len());
+    if from >= to {
+        return encode_text(text).into_owned();
+    }
+
+    let start = if from == 0 { 0 } else { words[from].0.start };
+    let end = if to == words.len() {
+        text.len()
+    } else {
+        words[to - 1].0.end
+    };
+
+    let mut res = String::new();
+    if from > 0 {
+        res.push_str("… ");
+    }
+    let mut pos = start;
+    for (range, word) in &words[from..to] {
+        if !is_match(word) {
+            continue;
+        }
+        res.push_str(&encode_text(&text[pos..range.start]));
+        res.push_str("<mark>");
+        res.push_str(&encode_text(&text[range.clone()]));
+        res.push_str("</mark>");
+        pos = range.end;
+    }
+    res.push_str(&encode_text(&text[pos..end]));
+    if to < words.len() {
+        res.push_str(" …");
+    }
+    res
+}
+
+#[must_use]
+pub fn html_to_text(html: &str) -> String {
+    Html::parse_fragment(html)
+        .root_element()
+        .text()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
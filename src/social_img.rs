@@ -1,4 +1,9 @@
-use axum::{extract::Path, response::IntoResponse, Extension};
+use axum::{
+    extract::Path,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Extension,
+};
 use image::{imageops::overlay, ImageBuffer, ImageFormat, RgbaImage};
 use imageproc::{
     drawing::{draw_filled_rect_mut, draw_text_mut, text_size},
@@ -14,7 +19,10 @@ use std::{
     sync::Mutex,
 };
 
-use crate::articles::ArticlesRepo;
+use crate::{
+    articles::{Article, ArticlesRepo},
+    conditional::{conditional, etag, not_modified_since},
+};
 
 const WIDTH: u32 = 1200;
 const HEIGHT: u32 = 600;
@@ -29,27 +37,41 @@ static mut LRU_CACHE: Lazy<Mutex<LruCache<String, Vec<u8>>>> =
     Lazy::new(|| Mutex::new(LruCache::new(NonZeroUsize::new(10).unwrap())));
 
 pub async fn social_image_article(
+    headers: HeaderMap,
     articles_repo: Extension<ArticlesRepo>,
     Path(slug): Path<String>,
-) -> impl IntoResponse {
-    let mut cache = unsafe { LRU_CACHE.lock() }.unwrap();
+) -> Response {
+    let Some(a) = articles_repo.get_article_by_slug(&slug) else {
+        return (StatusCode::NOT_FOUND, "404 not found").into_response();
+    };
+    let last_modified = articles_repo.loaded_at().max(a.datetime);
+    if let Some(res) = not_modified_since(&headers, last_modified) {
+        return res;
+    }
 
-    let bytes = cache.get_or_insert(slug.clone(), || _social_image_article(articles_repo, &slug));
+    let mut cache = unsafe { LRU_CACHE.lock() }.unwrap();
 
-    (
-        axum::response::AppendHeaders([
-            (CONTENT_TYPE, "image/png"),
-            (CACHE_CONTROL, "public, max-age=21600, immutable"),
-        ]),
-        bytes.clone(),
+    // the image shows the title and the date: a new one is drawn as soon as
+    // either changes
+    let shown = format!("{}\n{}", a.title, a.datetime);
+    let key = format!("{slug}-{:08x}", crc32fast::hash(shown.as_bytes()));
+    let bytes = cache.get_or_insert(key, || _social_image_article(&a));
+
+    conditional(
+        &headers,
+        &etag(bytes),
+        Some(last_modified),
+        (
+            axum::response::AppendHeaders([
+                (CONTENT_TYPE, "image/png"),
+                (CACHE_CONTROL, "public, max-age=21600, immutable"),
+            ]),
+            bytes.clone(),
+        ),
     )
 }
 
-fn _social_image_article(articles_repo: Extension<ArticlesRepo>, slug: &str) -> Vec<u8> {
-    let a = articles_repo
-        .get_article_by_slug(slug)
-        .unwrap_or_else(|| panic!("Article not found"));
-
+fn _social_image_article(a: &Article) -> Vec<u8> {
     let mut image: RgbaImage = ImageBuffer::new(WIDTH, HEIGHT);
     image.pixels_mut().for_each(|pixel| {
         *pixel = COLOR_BG;
@@ -0,0 +1,56 @@
+use std::collections::BTreeMap;
+
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+use crate::{apps::App, articles::Article, bookmarks::Bookmark};
+
+/// Tags are case-insensitive and use dashes instead of spaces, so that they
+/// can be used as-is in URLs.
+#[must_use]
+pub fn normalize_tag(tag: &str) -> String {
+    tag.split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-")
+        .to_lowercase()
+}
+
+/// Characters left as they are in the URL of a tag: the unreserved ones.
+const TAG_PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Path of the page of a tag, e.g. `/tags/c%23` for `c#`.
+#[must_use]
+pub fn tag_path(tag: &str) -> String {
+    format!("/tags/{}", utf8_percent_encode(tag, TAG_PATH_SEGMENT))
+}
+
+/// Parses a comma-separated list of tags, e.g. from a form input.
+pub fn parse_tags(input: &str) -> Vec<String> {
+    input
+        .split(',')
+        .map(normalize_tag)
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+/// Number of articles, bookmarks and apps using each tag, sorted by tag.
+#[must_use]
+pub fn count_tags(
+    articles: &[Article],
+    bookmarks: &[Bookmark],
+    apps: &[App],
+) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+    let tags = articles
+        .iter()
+        .flat_map(|a| &a.tags)
+        .chain(bookmarks.iter().flat_map(|b| &b.tags))
+        .chain(apps.iter().flat_map(|a| &a.tags));
+    for tag in tags {
+        *counts.entry(tag.clone()).or_default() += 1;
+    }
+    counts
+}
@@ -0,0 +1,579 @@
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    hash::BuildHasher,
+};
+
+use chrono::DateTime;
+use frontmatter::Yaml;
+
+use crate::{
+    articles::{linkable_titles, ARTICLES_DIR},
+    highlight::fenced_lines,
+    markdown::{load_dir, LoadError, MarkdownFile},
+    mdx::ComponentRegistry,
+    redirects::normalize_path,
+    wiki_links,
+};
+
+/// What the validation of articles found.
+#[derive(Debug, Default)]
+pub struct Report {
+    /// Problems that make an article invalid.
+    pub errors: Vec<LoadError>,
+    /// Links to scheduled or unlisted articles: they are rendered as text
+    /// until the article they point to is published and listed.
+    pub warnings: Vec<LoadError>,
+}
+
+impl Report {
+    fn extend(&mut self, other: Self) {
+        self.errors.extend(other.errors);
+        self.warnings.extend(other.warnings);
+    }
+}
+
+/// Validates every article and reports all the problems found.
+///
+/// Used by `univrs check` and at startup, so that a broken article is caught
+/// before the server starts rather than while rendering it.
+///
+/// # Errors
+///
+/// Returns an error if at least one problem was found. Warnings are only
+/// logged.
+pub async fn check(components: &ComponentRegistry) -> Result<(), Box<dyn Error>> {
+    let report = check_dir(ARTICLES_DIR, components).await;
+    for warning in &report.warnings {
+        tracing::warn!("{warning}");
+    }
+    if report.errors.is_empty() {
+        tracing::info!("no problems found in {ARTICLES_DIR}");
+        return Ok(());
+    }
+
+    for problem in &report.errors {
+        tracing::error!("{problem}");
+    }
+    Err(format!("found {} problem(s) in {ARTICLES_DIR}", report.errors.len()).into())
+}
+
+pub async fn check_dir(path: &str, components: &ComponentRegistry) -> Report {
+    let mut report = Report::default();
+    let mut files = vec![];
+    for md in load_dir(path) {
+        match md {
+            Ok(md) => files.push(md),
+            Err(err) => report.errors.push(err),
+        }
+    }
+
+    let titles = linkable_titles(&files);
+    let slugs = files
+        .iter()
+        .map(|md| md.name.clone())
+        .collect::<HashSet<_>>();
+    for md in &files {
+        report.errors.extend(check_file(md, components).await);
+        report.extend(check_links(md, &titles, &slugs));
+    }
+    report.errors.extend(check_slugs(&files));
+    report.errors.extend(check_aliases(&files));
+
+    report
+        .errors
+        .sort_by(|a, b| (&a.path, a.line).cmp(&(&b.path, b.line)));
+    report
+        .warnings
+        .sort_by(|a, b| (&a.path, a.line).cmp(&(&b.path, b.line)));
+    report
+}
+
+/// Checks the frontmatter and the MDX components of a single file, against
+/// the ones declared in `components`.
+pub async fn check_file(md: &MarkdownFile, components: &ComponentRegistry) -> Vec<LoadError> {
+    let mut problems = check_frontmatter(md);
+    problems.extend(check_components(md, components).await);
+    problems
+}
+
+/// A check of a frontmatter key. It is given the whole frontmatter, as some
+/// keys depend on others, and returns the problems with its key.
+type FrontmatterCheck = fn(&Yaml) -> Vec<String>;
+
+/// Checks of the frontmatter, by key.
+const FRONTMATTER_CHECKS: &[(&str, FrontmatterCheck)] = &[
+    ("title", check_title),
+    ("datetime", check_datetime),
+    ("publish_at", check_publish_at),
+    ("unlisted", check_unlisted),
+    ("tags", check_tags),
+    ("series", check_series),
+    ("series_order", check_series_order),
+    ("aliases", check_alias_paths),
+];
+
+fn check_frontmatter(md: &MarkdownFile) -> Vec<LoadError> {
+    FRONTMATTER_CHECKS
+        .iter()
+        .flat_map(|(key, check)| {
+            // missing keys are reported on the first line
+            let line = frontmatter_line(&md.source, key).unwrap_or(1);
+            check(&md.frontmatter)
+                .into_iter()
+                .map(move |problem| LoadError::at(&md.path, line, problem))
+        })
+        .collect()
+}
+
+fn check_title(fm: &Yaml) -> Vec<String> {
+    let title = &fm["title"];
+    if title.is_badvalue() {
+        vec!["missing `title`".to_string()]
+    } else if title.as_str().is_none() {
+        vec!["`title` must be a string".to_string()]
+    } else {
+        vec![]
+    }
+}
+
+fn check_datetime(fm: &Yaml) -> Vec<String> {
+    if fm["datetime"].is_badvalue() {
+        vec!["missing `datetime`".to_string()]
+    } else {
+        check_date(fm, "datetime")
+    }
+}
+
+fn check_publish_at(fm: &Yaml) -> Vec<String> {
+    check_date(fm, "publish_at")
+}
+
+/// Checks that `key`, if set, is an RFC3339 date.
+fn check_date(fm: &Yaml, key: &str) -> Vec<String> {
+    let value = &fm[key];
+    if value.is_badvalue() {
+        return vec![];
+    }
+    match value.as_str().map(DateTime::parse_from_rfc3339) {
+        Some(Ok(_)) => vec![],
+        Some(Err(err)) => vec![format!("`{key}` is not a valid RFC3339 date: {err}")],
+        None => vec![format!("`{key}` must be a string")],
+    }
+}
+
+fn check_unlisted(fm: &Yaml) -> Vec<String> {
+    let unlisted = &fm["unlisted"];
+    if !unlisted.is_badvalue() && unlisted.as_bool().is_none() {
+        vec!["`unlisted` must be a boolean".to_string()]
+    } else {
+        vec![]
+    }
+}
+
+fn check_tags(fm: &Yaml) -> Vec<String> {
+    let tags = &fm["tags"];
+    if !tags.is_badvalue()
+        && !tags
+            .as_vec()
+            .is_some_and(|tags| tags.iter().all(|t| t.as_str().is_some()))
+    {
+        vec!["`tags` must be a list of strings".to_string()]
+    } else {
+        vec![]
+    }
+}
+
+fn check_series(fm: &Yaml) -> Vec<String> {
+    let series = &fm["series"];
+    if !series.is_badvalue() && series.as_str().is_none() {
+        vec!["`series` must be a string".to_string()]
+    } else {
+        vec![]
+    }
+}
+
+fn check_series_order(fm: &Yaml) -> Vec<String> {
+    let series_order = &fm["series_order"];
+    if series_order.is_badvalue() {
+        vec![]
+    } else if series_order.as_i64().is_none() {
+        vec!["`series_order` must be an integer".to_string()]
+    } else if fm["series"].is_badvalue() {
+        vec!["`series_order` requires `series`".to_string()]
+    } else {
+        vec![]
+    }
+}
+
+fn check_alias_paths(fm: &Yaml) -> Vec<String> {
+    let aliases = &fm["aliases"];
+    if aliases.is_badvalue() {
+        return vec![];
+    }
+    match aliases.as_vec() {
+        Some(aliases) if aliases.iter().all(|a| a.as_str().is_some()) => aliases
+            .iter()
+            .filter_map(|a| a.as_str())
+            .filter(|alias| !alias.starts_with('/'))
+            .map(|alias| format!("alias `{alias}` must be an absolute path"))
+            .collect(),
+        _ => vec!["`aliases` must be a list of strings".to_string()],
+    }
+}
+
+/// Finds the line where `key` is defined in the frontmatter of `source`.
+fn frontmatter_line(source: &str, key: &str) -> Option<usize> {
+    source
+        .lines()
+        .enumerate()
+        .skip(1)
+        .take_while(|(_, line)| *line != "---")
+        .find(|(_, line)| {
+            line.strip_prefix(key)
+                .is_some_and(|rest| rest.trim_start().starts_with(':'))
+        })
+        .map(|(i, _)| i + 1)
+}
+
+async fn check_components(md: &MarkdownFile, components: &ComponentRegistry) -> Vec<LoadError> {
+    let offset = md.content_line() - 1;
+    let mut problems = vec![];
+    for c in find_components(&md.content) {
+        problems.extend(
+            components
+                .check(&c.name, &c.attributes)
+                .await
+                .into_iter()
+                .map(|message| LoadError::at(&md.path, offset + c.line, message)),
+        );
+    }
+    problems
+}
+
+/// Checks that the wiki links of a file point to existing articles, given
+/// the `titles` of the articles that can be linked to, by slug, and the
+/// `slugs` of all the articles.
+///
+/// Links to unknown articles are errors, links to scheduled or unlisted ones
+/// are warnings.
+pub fn check_links<S: BuildHasher, T: BuildHasher>(
+    md: &MarkdownFile,
+    titles: &HashMap<String, String, S>,
+    slugs: &HashSet<String, T>,
+) -> Report {
+    let offset = md.content_line() - 1;
+    let mut report = Report::default();
+    for link in wiki_links::find(&md.content) {
+        if titles.contains_key(&link.slug) {
+            continue;
+        }
+        let line = offset + link.line;
+        if slugs.contains(&link.slug) {
+            report.warnings.push(LoadError::at(
+                &md.path,
+                line,
+                format!(
+                    "link to scheduled or unlisted article `{}`, shown as text for now",
+                    link.slug
+                ),
+            ));
+        } else {
+            report.errors.push(LoadError::at(
+                &md.path,
+                line,
+                format!("link to unknown article `{}`", link.slug),
+            ));
+        }
+    }
+    report
+}
+
+fn check_slugs(files: &[MarkdownFile]) -> Vec<LoadError> {
+    let mut seen: HashMap<String, &MarkdownFile> = HashMap::new();
+    let mut problems = vec![];
+    for md in files {
+        // slugs are compared case-insensitively, as `Foo.md` and `foo.md`
+        // would collide on case-insensitive file systems
+        if let Some(other) = seen.get(&md.name.to_lowercase()) {
+            problems.push(LoadError::new(
+                &md.path,
+                format!(
+                    "duplicate slug `{}`, already used by {}",
+                    md.name,
+                    other.path.display()
+                ),
+            ));
+        } else {
+            seen.insert(md.name.to_lowercase(), md);
+        }
+    }
+    problems
+}
+
+/// Checks that every alias points to a single article, and that no alias
+/// shadows the path of an article.
+fn check_aliases(files: &[MarkdownFile]) -> Vec<LoadError> {
+    let slugs = files
+        .iter()
+        .map(|md| md.name.to_lowercase())
+        .collect::<HashSet<_>>();
+    let mut seen: HashMap<String, &MarkdownFile> = HashMap::new();
+    let mut problems = vec![];
+    for md in files {
+        let line = frontmatter_line(&md.source, "aliases").unwrap_or(1);
+        let aliases = md.frontmatter["aliases"]
+            .as_vec()
+            .map(|aliases| {
+                aliases
+                    .iter()
+                    .filter_map(|a| a.as_str())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        for alias in aliases {
+            let path = normalize_path(alias);
+            let shadowed = path
+                .strip_prefix("/articles/")
+                .is_some_and(|slug| slugs.contains(&slug.to_lowercase()));
+            if shadowed {
+                problems.push(LoadError::at(
+                    &md.path,
+                    line,
+                    format!("alias `{alias}` is the path of an existing article"),
+                ));
+            } else if let Some(other) = seen.get(&path) {
+                problems.push(LoadError::at(
+                    &md.path,
+                    line,
+                    format!(
+                        "duplicate alias `{alias}`, already used by {}",
+                        other.path.display()
+                    ),
+                ));
+            } else {
+                seen.insert(path, md);
+            }
+        }
+    }
+    problems
+}
+
+struct ComponentTag {
+    line: usize,
+    name: String,
+    attributes: HashMap<String, Option<String>>,
+}
+
+/// Finds the opening tags of MDX components (i.e. tags starting with an
+/// uppercase letter) in a markdown source, skipping code blocks and inline
+/// code. Lines are relative to the start of `content`.
+fn find_components(content: &str) -> Vec<ComponentTag> {
+    let mut tags = vec![];
+    let mut line_start = 0;
+    // tags can span multiple lines, don't look for other tags inside them
+    let mut consumed = 0;
+
+    for (i, (line, in_fence)) in fenced_lines(content).enumerate() {
+        let start = line_start;
+        line_start += line.len() + 1;
+        if in_fence {
+            continue;
+        }
+
+        let mut in_code = false;
+        for (j, c) in line.char_indices() {
+            if start + j < consumed {
+                continue;
+            }
+            match c {
+                '`' => in_code = !in_code,
+                '<' if !in_code && line[j + 1..].starts_with(|c: char| c.is_ascii_uppercase()) => {
+                    let (tag, len) = parse_tag(i + 1, &content[start + j + 1..]);
+                    consumed = start + j + 1 + len;
+                    tags.push(tag);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    tags
+}
+
+/// Parses the name and attributes of a tag, `input` being everything after
+/// the opening `<`. Returns the tag and its length in bytes.
+fn parse_tag(line: usize, input: &str) -> (ComponentTag, usize) {
+    let end = input.find('>').unwrap_or(input.len());
+    let inner = input[..end].trim_end_matches('/');
+
+    let name_len = inner
+        .find(|c: char| !c.is_ascii_alphanumeric())
+        .unwrap_or(inner.len());
+    let name = inner[..name_len].to_string();
+
+    let mut attributes = HashMap::new();
+    let mut rest = inner[name_len..].trim_start();
+    while !rest.is_empty() {
+        let key_len = rest
+            .find(|c: char| c.is_whitespace() || c == '=')
+            .unwrap_or(rest.len());
+        let key = rest[..key_len].to_string();
+        rest = rest[key_len..].trim_start();
+
+        let value = if let Some(after_eq) = rest.strip_prefix('=') {
+            let after_eq = after_eq.trim_start();
+            let (value, remaining) = if let Some(quote @ ('"' | '\'')) = after_eq.chars().next() {
+                let value_end = after_eq[1..].find(quote).map_or(after_eq.len(), |i| i + 1);
+                (
+                    &after_eq[1..value_end],
+                    after_eq.get(value_end + 1..).unwrap_or_default(),
+                )
+            } else {
+                let value_end = after_eq.find(char::is_whitespace).unwrap_or(after_eq.len());
+                (&after_eq[..value_end], &after_eq[value_end..])
+            };
+            rest = remaining.trim_start();
+            Some(value.to_string())
+        } else {
+            None
+        };
+
+        if !key.is_empty() {
+            attributes.insert(key, value);
+        }
+    }
+
+    let tag = ComponentTag {
+        line,
+        name,
+        attributes,
+    };
+    (tag, end + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use super::*;
+
+    /// Writes `files` as `(slug, source)` into a new directory.
+    fn articles_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("univrs-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for (slug, source) in files {
+            fs::write(dir.join(format!("{slug}.md")), source).unwrap();
+        }
+        dir
+    }
+
+    #[tokio::test]
+    async fn links_to_scheduled_articles_are_warnings() {
+        let dir = articles_dir(
+            "scheduled-link",
+            &[
+                (
+                    "a",
+                    "---\ntitle: A\ndatetime: 2023-01-01T00:00:00Z\n---\nSee [[b]].\n",
+                ),
+                (
+                    "b",
+                    "---\ntitle: B\ndatetime: 2023-01-01T00:00:00Z\npublish_at: 2099-01-01T00:00:00Z\n---\nSoon.\n",
+                ),
+            ],
+        );
+
+        let report = check_dir(dir.to_str().unwrap(), &ComponentRegistry::default()).await;
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+        assert_eq!(report.warnings.len(), 1);
+        assert_eq!(report.warnings[0].line, Some(5));
+    }
+
+    #[tokio::test]
+    async fn links_to_unknown_articles_are_errors() {
+        let dir = articles_dir(
+            "unknown-link",
+            &[(
+                "a",
+                "---\ntitle: A\ndatetime: 2023-01-01T00:00:00Z\n---\nSee [[missing]].\n",
+            )],
+        );
+
+        let report = check_dir(dir.to_str().unwrap(), &ComponentRegistry::default()).await;
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(report.errors.len(), 1);
+        assert!(report.errors[0].message.contains("`missing`"));
+        assert!(report.warnings.is_empty());
+    }
+
+    #[tokio::test]
+    async fn reports_frontmatter_problems_on_their_line() {
+        let dir = articles_dir(
+            "frontmatter",
+            &[(
+                "a",
+                "---\ndatetime: yesterday\nseries_order: 2\naliases: [\"/old\", \"new\"]\n---\nA\n",
+            )],
+        );
+
+        let report = check_dir(dir.to_str().unwrap(), &ComponentRegistry::default()).await;
+        fs::remove_dir_all(&dir).unwrap();
+
+        let problems = report
+            .errors
+            .iter()
+            .map(|e| (e.line.unwrap(), e.message.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(problems.len(), 4);
+        assert_eq!(problems[0], (1, "missing `title`"));
+        assert_eq!(problems[1].0, 2);
+        assert!(problems[1]
+            .1
+            .starts_with("`datetime` is not a valid RFC3339 date"));
+        assert_eq!(problems[2], (3, "`series_order` requires `series`"));
+        assert_eq!(problems[3], (4, "alias `new` must be an absolute path"));
+    }
+
+    #[test]
+    fn parses_tags() {
+        let (tag, len) = parse_tag(3, r#"Dialog character="bulb" pos=left open>Hi"#);
+        assert_eq!(len, 38);
+        assert_eq!(tag.line, 3);
+        assert_eq!(tag.name, "Dialog");
+        assert_eq!(
+            tag.attributes,
+            HashMap::from([
+                ("character".to_string(), Some("bulb".to_string())),
+                ("pos".to_string(), Some("left".to_string())),
+                ("open".to_string(), None),
+            ])
+        );
+
+        let (tag, _) = parse_tag(1, "AppCard slug='a b' />");
+        assert_eq!(tag.attributes["slug"].as_deref(), Some("a b"));
+    }
+
+    #[test]
+    fn finds_components_outside_of_code() {
+        let content =
+            "<Callout type=note>\n`<Inline />` <a>\n```\n<Fenced />\n```\n<Tab\n  label=\"x\">";
+        let tags = find_components(content)
+            .into_iter()
+            .map(|t| (t.line, t.name))
+            .collect::<Vec<_>>();
+        assert_eq!(tags, [(1, "Callout".to_string()), (6, "Tab".to_string())]);
+
+        let nested = "````md\n```\n<Fenced />\n```\n<StillFenced />\n````\n<Callout>";
+        let names = find_components(nested)
+            .into_iter()
+            .map(|t| t.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["Callout"]);
+    }
+}
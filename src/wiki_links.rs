@@ -0,0 +1,140 @@
+use std::{collections::HashMap, fmt::Write, hash::BuildHasher, ops::Range};
+
+use crate::{highlight::fenced_lines, markdown::escape};
+
+/// A `[[slug]]` or `[[slug|label]]` link to an article.
+#[derive(Clone, Debug)]
+pub struct WikiLink {
+    /// Line of the link, relative to the start of the markdown.
+    pub line: usize,
+    pub slug: String,
+    pub label: Option<String>,
+    range: Range<usize>,
+}
+
+/// Finds the wiki links of a markdown source, skipping code blocks and
+/// inline code.
+#[must_use]
+pub fn find(markdown: &str) -> Vec<WikiLink> {
+    let mut links = vec![];
+    let mut line_start = 0;
+
+    for (i, (line, in_fence)) in fenced_lines(markdown).enumerate() {
+        let start = line_start;
+        line_start += line.len() + 1;
+        if in_fence {
+            continue;
+        }
+
+        let mut in_code = false;
+        let mut j = 0;
+        while let Some(c) = line[j..].chars().next() {
+            let rest = &line[j..];
+            if c == '`' {
+                in_code = !in_code;
+            } else if !in_code && rest.starts_with("[[") {
+                if let Some(link) = parse(i + 1, start + j, rest) {
+                    j = link.range.end - start;
+                    links.push(link);
+                    continue;
+                }
+            }
+            j += c.len_utf8();
+        }
+    }
+
+    links
+}
+
+/// Parses the link at the start of `input`, which is at `offset` in the
+/// markdown.
+fn parse(line: usize, offset: usize, input: &str) -> Option<WikiLink> {
+    let end = input.find("]]")?;
+    let inner = &input[2..end];
+    if inner.contains('[') {
+        return None;
+    }
+
+    let (slug, label) = match inner.split_once('|') {
+        Some((slug, label)) => (slug.trim(), Some(label.trim())),
+        None => (inner.trim(), None),
+    };
+    if slug.is_empty() || slug.contains(char::is_whitespace) {
+        return None;
+    }
+
+    Some(WikiLink {
+        line,
+        slug: slug.to_string(),
+        label: label.filter(|l| !l.is_empty()).map(ToString::to_string),
+        range: offset..offset + end + 2,
+    })
+}
+
+/// Replaces the wiki links of `markdown` with links to the articles, given
+/// the `titles` of the articles by slug. Links without a label get the
+/// title of the article they point to.
+///
+/// Links to articles missing from `titles` are left as text, as the label or
+/// the slug: they are reported by the validation of articles.
+pub fn resolve<S: BuildHasher>(markdown: &str, titles: &HashMap<String, String, S>) -> String {
+    let mut res = String::with_capacity(markdown.len());
+    let mut last = 0;
+    for link in find(markdown) {
+        res.push_str(&markdown[last..link.range.start]);
+        match (titles.get(&link.slug), link.label) {
+            (Some(_), Some(label)) => {
+                let _ = write!(res, "[{label}](/articles/{})", link.slug);
+            }
+            (Some(title), None) => {
+                let _ = write!(res, "[{}](/articles/{})", escape(title), link.slug);
+            }
+            (None, Some(label)) => res.push_str(&label),
+            (None, None) => res.push_str(&escape(&link.slug)),
+        }
+        last = link.range.end;
+    }
+    res.push_str(&markdown[last..]);
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_links_with_and_without_labels() {
+        let links = find("See [[a]] and\n[[b | the B]].");
+        assert_eq!(links.len(), 2);
+        assert_eq!((links[0].line, links[0].slug.as_str()), (1, "a"));
+        assert_eq!(links[0].label, None);
+        assert_eq!((links[1].line, links[1].slug.as_str()), (2, "b"));
+        assert_eq!(links[1].label.as_deref(), Some("the B"));
+    }
+
+    #[test]
+    fn skips_code() {
+        let links = find("`[[a]]`\n```\n[[b]]\n```\n[[c]]");
+        let slugs = links.iter().map(|l| l.slug.as_str()).collect::<Vec<_>>();
+        assert_eq!(slugs, ["c"]);
+
+        let links = find("````md\n```\n[[a]]\n```\n[[b]]\n````\n[[c]]");
+        let slugs = links.iter().map(|l| l.slug.as_str()).collect::<Vec<_>>();
+        assert_eq!(slugs, ["c"]);
+    }
+
+    #[test]
+    fn skips_invalid_links() {
+        assert_eq!(find("[[]] [[a b]] [[a [[b]]").len(), 1);
+        assert!(find("[[unclosed").is_empty());
+    }
+
+    #[test]
+    fn resolves_links() {
+        let titles = HashMap::from([("a".to_string(), "Title *A*".to_string())]);
+        assert_eq!(
+            resolve("[[a]], [[a|here]], [[b]], [[b|there]]", &titles),
+            r"[Title \*A\*](/articles/a), [here](/articles/a), b, there"
+        );
+    }
+}